@@ -1,8 +1,27 @@
+## Generic messages
+# Name of this language, as shown in the language list
+lang-name = English (US)
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $error (String) - Error that was reported
+error-generic =
+    An error occurred, it has been reported and will be fixed soon { $sad-emoji }
+    ```
+    { $error }
+    ```
+embedlist-back = < Prev.
+embedlist-next = Next >
+
 ## Japanese command
 japanese = japanese
     .desc = Searches in Jisho for the word
-japenese-options-word = word
+japanese-options-word = word
     .desc = Can be a kanji, a japanese word or even an english word (Same search features as Jisho)
+japanese-common = common word
+# $level (Number) - WaniKani level the word is taught in
+japanese-wanikani = wanikani lvl{ $level }
+# $word (String) - Form of the word the sense is restricted to
+japanese-restriction = Only applies to { $word }
+japanese-other-forms = Other forms
 
 ## Anime opening command
 op = op
@@ -18,48 +37,253 @@ op-notuploaded = This theme is yet to be uploaded. { $sad-emoji }
 # What appears before the video link
 # $title (String) - Anime theme's title
 # $artist (String) - Artist's name (can be a band)
-op-message-prelude = **{ $title }** {$artist ->
-    [none] { "" }
-    *[some] from { $artist }
-}
+# $link (String) - Link to the theme's video
+op-message-prelude = {$title ->
+        [none] { "" }
+       *[some] **{ $title }**
+    }{$artist ->
+        [none] { "" }
+       *[some] { " " }from { $artist }
+    }
     { $link }
+# Button of other commands that looks up the themes of an anime
+op-lookup = Openings and endings
 
 ## Sauce command
 sauce = sauce
     .desc = Searches the image's original source
 sauce-options-image = image
     .desc = Image to reverse-lookup for
+sauce-options-url = url
+    .desc = Link to an image to reverse-lookup for
+sauce-options-image2 = image2
+    .desc = Another image to reverse-lookup for
+sauce-options-image3 = image3
+    .desc = Another image to reverse-lookup for
+sauce-options-database = database
+    .desc = Sites to search in, all of them by default
+sauce-options-database-all = All sites
+sauce-options-database-anime = Anime, movies and shows
+sauce-options-database-booru = Boorus
+sauce-options-database-pixiv = Pixiv
+sauce-options-database-manga = Manga and doujinshi
+sauce-options-database-art = Art sites and social media
+sauce-options-results = results
+    .desc = How many results to show, 5 by default
+sauce-options-similarity = similarity
+    .desc = Leaves out the results less similar than this percentage
+sauce-options-clip = clip
+    .desc = Attaches a short clip of the scene instead of a still
+sauce-options-cut_borders = cut_borders
+    .desc = Removes the black borders of the image before searching
+sauce-options-anime = anime
+    .desc = Only looks for the scene in this anime
 sauce-nsfw = **WARNING**: Image is NSFW so it's been censored!
+sauce-nsfw-hidden = **WARNING**: Image is NSFW so it's been left out!
+sauce-nsfw-spoiler = **WARNING**: Image is NSFW so it's been sent behind a spoiler!
+# Footer of the results that came from the cache
+sauce-cached = cached
+# $num (Number) - Which of the images the results are of
+# $total (Number) - How many images were searched
+# $name (String) - File name of the image
+sauce-image-label = Image { $num } of { $total }: `{ $name }`
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-noimage = Send an image or a link to one to search its sauce { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-badurl = That link can't be searched, it has to be a public http or https one { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $size (Number) - Biggest size allowed in MB
+sauce-toolarge = The image is too big, it can't go over { $size } MB { $sad-emoji }
 
 
+## Sauce auto command
+sauce-auto = auto
+    .desc = Searches the image's source with every engine at once
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-auto-empty = None of the engines found anything { $sad-emoji }
+
 ## Sauce trace.moe command
 sauce-tracemoe = tracemoe
     .desc = Searches the image's source with trace.moe
+# $amount (String) - Similarity percentage, already rounded
 sauce-tracemoe-similarity = Similarity:
-    .value = { NUMBER($amount, maximumFractionDigits: 2, style: "percent") }
+    .value = { $amount }%
+# $from (String) - When the scene starts in the episode
+# $to (String) - When the scene ends
+# $num (String) - Episode number or name, a range like 1–3 in .episodes
 sauce-tracemoe-timestamp = Timestamp:
-    .value = Episode { $num } at { $timestamp }
+    .value = { $from }–{ $to }
+    .episode = Episode { $num } at { $from }–{ $to }
+    .episodes = Episodes { $num } at { $from }–{ $to }
+# Native title, romaji title and synonyms of the anime
+sauce-tracemoe-aliases = Also known as:
+# AniList and MyAnimeList links of the anime
+sauce-tracemoe-links = Links:
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-tracemoe-empty = trace.moe didn't find anything { $sad-emoji }
+sauce-tracemoe-clip-nsfw = The clip can only be sent in NSFW channels
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $error (String) - What trace.moe answered
+sauce-tracemoe-error = trace.moe couldn't search the image: { $error } { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-tracemoe-noanime = There's no anime with that name in AniList { $sad-emoji }
+# Footer of the results, trace.moe's quota resets every month
+# $left (Number) - Searches left this month
+sauce-tracemoe-quota = { $left ->
+    [one] 1 search left
+   *[other] { $left } searches left
+}
 
 ## Sauce saucenao command
 sauce-saucenao = saucenao
     .desc = Searches the image's source with SauceNAO
-sauce-saucenao-similarity = Similarity { NUMBER($amount, maximumFractionDigits: 2, style: "percent") }
+# $amount (String) - Similarity percentage as given by SauceNAO
+sauce-saucenao-similarity = Similarity { $amount }%
 sauce-saucenao-part = Part:
     .value = { $num }
 sauce-saucenao-timestamp = Timestamp:
-    .value = { $timestamp }/{ $length }
+    .value = { $timestamp }
 sauce-saucenao-tweet = Tweet by { $user-handle }
 sauce-saucenao-toot = Toot by { $pawoo-user }
 sauce-saucenao-mangadex-authors = { $author }{$artist ->
     [none] { "" }
     *[artist] , { $artist }
 }
+# $type (String) - Kind of part, either "chapter" or "volume"
+# $num (String) - Number of the part
 sauce-saucenao-mangadex-part = Part:
     .value = {$type ->
-    *[Chapter] Chapter { $num }
+    [volume] Volume { $num }
+    *[chapter] Chapter { $num }
 }
 sauce-saucenao-skeb-title = Artwork request
+# $type (String) - Kind of part, either "chapter" or "volume"
+# $num (String) - Number of the part
 sauce-saucenao-hmag-part = Part:
     .value = {$type ->
-    *[vol] Volume { $num }
+    [chapter] Chapter { $num }
+    *[volume] Volume { $num }
 }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-saucenao-disabled = SauceNAO searches aren't set up in this bot { $sad-emoji }
+# $amount (String) - Minimum similarity percentage asked for
+sauce-saucenao-min-similarity = { $amount }% similar or more
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-saucenao-empty = SauceNAO didn't find anything { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $amount (String) - Minimum similarity percentage asked for
+sauce-saucenao-nomatch = None of the results are at least { $amount }% similar { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $status (Number) - Status code returned by SauceNAO
+# $retry (String) - When searching works again, as a Discord relative timestamp
+sauce-saucenao-unavailable = Seems that SauceNAO is not having a good time currently { $sad-emoji } ``(status code: { $status })``
+    .ratelimited = Seems that SauceNAO is not having a good time currently { $sad-emoji } We ran out of searches for now, try again { $retry }
+sauce-saucenao-error =
+    Sorry, an error happened { $sad-emoji } ``(status code: { $status })``
+    Did you send a valid image?
+
+## Sauce iqdb command
+sauce-iqdb = iqdb
+    .desc = Searches the image's source in boorus with IQDB
+# $amount (String) - Similarity percentage as given by IQDB
+sauce-iqdb-similarity = Similarity { $amount }%
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-iqdb-empty = IQDB didn't find anything { $sad-emoji }
+
+## Sauce ascii2d command
+sauce-ascii2d = ascii2d
+    .desc = Searches the image's source in art sites with ascii2d
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-ascii2d-empty = ascii2d didn't find anything { $sad-emoji }
+sauce-ascii2d-unrated = ascii2d doesn't say if images are NSFW, so outside NSFW channels they're handled like they are
+
+## Find sauce message command
+find-sauce = Find sauce
+find-sauce-pick = Which image do you want the sauce of?
+# $origin (String) - Where the image is in the message, either "attachment", "embed" or "link"
+find-sauce-origin = {$origin ->
+        [attachment] Attachment
+        [embed] Embed
+       *[link] Link
+    }
+# $sad-emoji (String) - Sad emoji used when errors happen
+find-sauce-noimages = There aren't any images in that message { $sad-emoji }
+
+## Settings command
+settings = settings
+    .desc = Changes how the bot behaves
+settings-language = language
+    .desc = Changes the language the bot replies in
+settings-options-language = language
+    .desc = Language to use, leave it empty to go back to the default one
+settings-options-server = server
+    .desc = Changes the default language of the whole server instead of yours
+# $scope (String) - Whose language was changed, either "server" or "user"
+# $lang (String) - Name of the new language, from lang-name
+settings-language-updated = {$scope ->
+        [server] The server's language is now { $lang }
+       *[user] Your language is now { $lang }
+    }
+# $scope (String) - Whose language was changed, either "server" or "user"
+settings-language-reset = {$scope ->
+        [server] The server's language is back to the default one
+       *[user] Your language is back to the default one
+    }
+# $lang (String) - Language code the user sent
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-invalid = { $lang } isn't one of the available languages { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-noguild = The server's language can only be changed inside a server { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-nopermission = You need the Manage Server permission to change the server's language { $sad-emoji }
+settings-censor = censor
+    .desc = Changes how NSFW images get censored outside NSFW channels in the server
+settings-options-method = method
+    .desc = How to censor them, leave it empty to pixelate them like by default
+settings-options-method-pixelate = Pixelate
+settings-options-method-blur = Blur
+settings-options-method-overlay = Cover with a label
+settings-options-strength = strength
+    .desc = How little of the image can be made out, 50 by default
+# $method (String) - Name of the censoring method, from settings-options-method-*
+# $strength (Number) - Between 1 and 100
+settings-censor-updated = NSFW images in this server now get censored with: { $method } (strength { $strength })
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-censor-noguild = Censoring can only be changed inside a server { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-censor-nopermission = You need the Manage Server permission to change how the server censors images { $sad-emoji }
+settings-nsfw = nsfw
+    .desc = Changes what happens to NSFW images outside NSFW channels in the server
+settings-options-policy = policy
+    .desc = What to do with them, leave it empty to censor them like by default
+settings-options-policy-censor = Censor them
+settings-options-policy-hide = Leave them out
+settings-options-policy-spoiler = Send them behind a spoiler
+settings-options-policy-allow = Show them as they are
+settings-options-sites = sites
+    .desc = Treats everything from adult sites as NSFW, even if it's rated safe
+# $policy (String) - Name of the policy, from settings-options-policy-*
+# $sites (String) - Whether results from adult sites count as NSFW, either "true" or "false"
+settings-nsfw-updated = {$sites ->
+        [true] What happens to NSFW images in this server: { $policy }. Everything from adult sites counts as NSFW
+       *[false] What happens to NSFW images in this server: { $policy }
+    }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-nsfw-noguild = What happens to NSFW images can only be changed inside a server { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-nsfw-nopermission = You need the Manage Server permission to change what happens to NSFW images { $sad-emoji }
+
+## Admin command
+admin = admin
+    .desc = Maintenance of the bot, only for its admins
+admin-purge-cache = purge-cache
+    .desc = Forgets the cached reverse image searches
+admin-purge-cache-done = Forgot every cached search, the next ones go to the services again
+# $sad-emoji (String) - Sad emoji used when errors happen
+admin-nopermission = Only the admins of the bot can do this { $sad-emoji }
+
+## Draw command
+draw = draw
+    .desc = Draws the requested image
+draw-options-list = prompt
+    .desc = What do you want me to draw?
@@ -1,8 +1,27 @@
+## Generic messages
+# Name of this language, as shown in the language list
+lang-name = Español
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $error (String) - Error that was reported
+error-generic =
+    Ocurrio un error, ya fue reportado y sera arreglado pronto { $sad-emoji }
+    ```
+    { $error }
+    ```
+embedlist-back = < Ant.
+embedlist-next = Sig. >
+
 ## Japanese command
 japanese = japones
     .desc = Busca en Jisho por la palabra
-japenese-options-word = palabra
+japanese-options-word = palabra
     .desc = Puede ser un kanji, silabas japonesas o incluso texto en ingles. (Funciona igual que Jisho)
+japanese-common = palabra comun
+# $level (Number) - WaniKani level the word is taught in
+japanese-wanikani = wanikani nvl{ $level }
+# $word (String) - Form of the word the sense is restricted to
+japanese-restriction = Solo aplica a { $word }
+japanese-other-forms = Otras formas
 
 ## Anime opening command
 op = op
@@ -18,48 +37,253 @@ op-notuploaded = This theme is yet to be uploaded. { $sad-emoji }
 # What appears before the video link
 # $title (String) - Anime theme's title
 # $artist (String) - Artist's name (can be a band)
-op-message-prelude = **{ $title }** {$artist ->
-    [none] { "" }
-    *[some] from { $artist }
-}
+# $link (String) - Link to the theme's video
+op-message-prelude = {$title ->
+        [none] { "" }
+       *[some] **{ $title }**
+    }{$artist ->
+        [none] { "" }
+       *[some] { " " }de { $artist }
+    }
     { $link }
+# Button of other commands that looks up the themes of an anime
+op-lookup = Openings y endings
 
 ## Sauce command
 sauce = sauce
     .desc = Searches the image's original source
 sauce-options-image = image
     .desc = Image to reverse-lookup for
-sauce-nsfw = **WARNING**: Image is NSFW so it's been censored!
+sauce-options-url = url
+    .desc = Link a una imagen para buscar
+sauce-options-image2 = image2
+    .desc = Otra imagen para buscar
+sauce-options-image3 = image3
+    .desc = Otra imagen para buscar
+sauce-options-database = base-de-datos
+    .desc = Sitios en los que buscar, todos por defecto
+sauce-options-database-all = Todos los sitios
+sauce-options-database-anime = Anime, peliculas y series
+sauce-options-database-booru = Boorus
+sauce-options-database-pixiv = Pixiv
+sauce-options-database-manga = Manga y doujinshi
+sauce-options-database-art = Sitios de arte y redes sociales
+sauce-options-results = resultados
+    .desc = Cuantos resultados mostrar, 5 por defecto
+sauce-options-similarity = similitud
+    .desc = Deja afuera los resultados con menos similitud que este porcentaje
+sauce-options-clip = clip
+    .desc = Adjunta un clip corto de la escena en vez de una imagen
+sauce-options-cut_borders = quitar_bordes
+    .desc = Quita los bordes negros de la imagen antes de buscar
+sauce-options-anime = anime
+    .desc = Solo busca la escena en este anime
+sauce-nsfw = **CUIDADO**: La imagen es NSFW asi que fue censurada!
+sauce-nsfw-hidden = **CUIDADO**: La imagen es NSFW asi que no se muestra!
+sauce-nsfw-spoiler = **CUIDADO**: La imagen es NSFW asi que se envio como spoiler!
+# Footer of the results that came from the cache
+sauce-cached = en cache
+# $num (Number) - Which of the images the results are of
+# $total (Number) - How many images were searched
+# $name (String) - File name of the image
+sauce-image-label = Imagen { $num } de { $total }: `{ $name }`
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-noimage = Mandá una imagen o un link a una para buscar su origen { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-badurl = No se puede buscar ese link, tiene que ser uno publico http o https { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $size (Number) - Biggest size allowed in MB
+sauce-toolarge = La imagen es muy grande, no puede pasar de { $size } MB { $sad-emoji }
 
 
+## Sauce auto command
+sauce-auto = auto
+    .desc = Busca el origen de la imagen con todos los buscadores a la vez
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-auto-empty = Ninguno de los buscadores encontro nada { $sad-emoji }
+
 ## Sauce trace.moe command
 sauce-tracemoe = tracemoe
     .desc = Searches the image's source with trace.moe
+# $amount (String) - Similarity percentage, already rounded
 sauce-tracemoe-similarity = Similarity:
-    .value = { NUMBER($amount, maximumFractionDigits: 2, style: "percent") }
+    .value = { $amount }%
+# $from (String) - When the scene starts in the episode
+# $to (String) - When the scene ends
+# $num (String) - Episode number or name, a range like 1–3 in .episodes
 sauce-tracemoe-timestamp = Timestamp:
-    .value = Episode { $num } at { $timestamp }
+    .value = { $from }–{ $to }
+    .episode = Episode { $num } at { $from }–{ $to }
+    .episodes = Episodes { $num } at { $from }–{ $to }
+# Native title, romaji title and synonyms of the anime
+sauce-tracemoe-aliases = Tambien conocido como:
+# AniList and MyAnimeList links of the anime
+sauce-tracemoe-links = Links:
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-tracemoe-empty = trace.moe no encontro nada { $sad-emoji }
+sauce-tracemoe-clip-nsfw = El clip solo se puede mandar en canales NSFW
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $error (String) - What trace.moe answered
+sauce-tracemoe-error = trace.moe no pudo buscar la imagen: { $error } { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-tracemoe-noanime = No hay ningun anime con ese nombre en AniList { $sad-emoji }
+# Footer of the results, trace.moe's quota resets every month
+# $left (Number) - Searches left this month
+sauce-tracemoe-quota = { $left ->
+    [one] queda 1 busqueda
+   *[other] quedan { $left } busquedas
+}
 
 ## Sauce saucenao command
 sauce-saucenao = saucenao
     .desc = Searches the image's source with SauceNAO
-sauce-saucenao-similarity = Similarity { NUMBER($amount, maximumFractionDigits: 2, style: "percent") }
+# $amount (String) - Similarity percentage as given by SauceNAO
+sauce-saucenao-similarity = Similarity { $amount }%
 sauce-saucenao-part = Part:
     .value = { $num }
 sauce-saucenao-timestamp = Timestamp:
-    .value = { $timestamp }/{ $length }
+    .value = { $timestamp }
 sauce-saucenao-tweet = Tweet by { $user-handle }
 sauce-saucenao-toot = Toot by { $pawoo-user }
 sauce-saucenao-mangadex-authors = { $author }{$artist ->
     [none] { "" }
     *[artist] , { $artist }
 }
+# $type (String) - Kind of part, either "chapter" or "volume"
+# $num (String) - Number of the part
 sauce-saucenao-mangadex-part = Part:
     .value = {$type ->
-    *[Chapter] Chapter { $num }
+    [volume] Volume { $num }
+    *[chapter] Chapter { $num }
 }
 sauce-saucenao-skeb-title = Artwork request
+# $type (String) - Kind of part, either "chapter" or "volume"
+# $num (String) - Number of the part
 sauce-saucenao-hmag-part = Part:
     .value = {$type ->
-    *[vol] Volume { $num }
+    [chapter] Chapter { $num }
+    *[volume] Volume { $num }
 }
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-saucenao-disabled = Las busquedas de SauceNAO no estan configuradas en este bot { $sad-emoji }
+# $amount (String) - Minimum similarity percentage asked for
+sauce-saucenao-min-similarity = { $amount }% de similitud o mas
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-saucenao-empty = SauceNAO no encontro nada { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $amount (String) - Minimum similarity percentage asked for
+sauce-saucenao-nomatch = Ninguno de los resultados tiene al menos { $amount }% de similitud { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+# $status (Number) - Status code returned by SauceNAO
+# $retry (String) - When searching works again, as a Discord relative timestamp
+sauce-saucenao-unavailable = Parece que SauceNAO no la esta pasando bien ahora { $sad-emoji } ``(codigo de estado: { $status })``
+    .ratelimited = Parece que SauceNAO no la esta pasando bien ahora { $sad-emoji } Nos quedamos sin busquedas por ahora, intentá de nuevo { $retry }
+sauce-saucenao-error =
+    Perdon, ocurrio un error { $sad-emoji } ``(codigo de estado: { $status })``
+    Mandaste una imagen valida?
+
+## Sauce iqdb command
+sauce-iqdb = iqdb
+    .desc = Busca el origen de la imagen en boorus con IQDB
+# $amount (String) - Similarity percentage as given by IQDB
+sauce-iqdb-similarity = Similitud { $amount }%
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-iqdb-empty = IQDB no encontro nada { $sad-emoji }
+
+## Sauce ascii2d command
+sauce-ascii2d = ascii2d
+    .desc = Busca el origen de la imagen en sitios de arte con ascii2d
+# $sad-emoji (String) - Sad emoji used when errors happen
+sauce-ascii2d-empty = ascii2d no encontro nada { $sad-emoji }
+sauce-ascii2d-unrated = ascii2d no dice si las imagenes son NSFW, asi que fuera de los canales NSFW se tratan como si lo fueran
+
+## Find sauce message command
+find-sauce = Buscar sauce
+find-sauce-pick = ¿De que imagen querés la sauce?
+# $origin (String) - Where the image is in the message, either "attachment", "embed" or "link"
+find-sauce-origin = {$origin ->
+        [attachment] Archivo adjunto
+        [embed] Embed
+       *[link] Enlace
+    }
+# $sad-emoji (String) - Sad emoji used when errors happen
+find-sauce-noimages = No hay ninguna imagen en ese mensaje { $sad-emoji }
+
+## Settings command
+settings = ajustes
+    .desc = Cambia como se comporta el bot
+settings-language = idioma
+    .desc = Cambia el idioma en el que responde el bot
+settings-options-language = idioma
+    .desc = Idioma a usar, dejalo vacio para volver al predeterminado
+settings-options-server = servidor
+    .desc = Cambia el idioma predeterminado de todo el servidor en vez del tuyo
+# $scope (String) - Whose language was changed, either "server" or "user"
+# $lang (String) - Name of the new language, from lang-name
+settings-language-updated = {$scope ->
+        [server] El idioma del servidor ahora es { $lang }
+       *[user] Tu idioma ahora es { $lang }
+    }
+# $scope (String) - Whose language was changed, either "server" or "user"
+settings-language-reset = {$scope ->
+        [server] El idioma del servidor volvio al predeterminado
+       *[user] Tu idioma volvio al predeterminado
+    }
+# $lang (String) - Language code the user sent
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-invalid = { $lang } no es uno de los idiomas disponibles { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-noguild = El idioma del servidor solo se puede cambiar dentro de un servidor { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-language-nopermission = Necesitás el permiso de Gestionar Servidor para cambiar el idioma del servidor { $sad-emoji }
+settings-censor = censura
+    .desc = Cambia como se censuran las imagenes NSFW fuera de los canales NSFW en el servidor
+settings-options-method = metodo
+    .desc = Como censurarlas, dejalo vacio para pixelarlas como por defecto
+settings-options-method-pixelate = Pixelar
+settings-options-method-blur = Desenfocar
+settings-options-method-overlay = Tapar con una etiqueta
+settings-options-strength = intensidad
+    .desc = Lo poco que se puede distinguir de la imagen, 50 por defecto
+# $method (String) - Name of the censoring method, from settings-options-method-*
+# $strength (Number) - Between 1 and 100
+settings-censor-updated = Las imagenes NSFW de este servidor ahora se censuran con: { $method } (intensidad { $strength })
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-censor-noguild = La censura solo se puede cambiar dentro de un servidor { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-censor-nopermission = Necesitás el permiso de Gestionar Servidor para cambiar como censura el servidor { $sad-emoji }
+settings-nsfw = nsfw
+    .desc = Cambia que pasa con las imagenes NSFW fuera de los canales NSFW en el servidor
+settings-options-policy = politica
+    .desc = Que hacer con ellas, dejalo vacio para censurarlas como por defecto
+settings-options-policy-censor = Censurarlas
+settings-options-policy-hide = No mostrarlas
+settings-options-policy-spoiler = Enviarlas como spoiler
+settings-options-policy-allow = Mostrarlas tal cual
+settings-options-sites = sitios
+    .desc = Trata todo lo de sitios para adultos como NSFW, aunque se considere seguro
+# $policy (String) - Name of the policy, from settings-options-policy-*
+# $sites (String) - Whether results from adult sites count as NSFW, either "true" or "false"
+settings-nsfw-updated = {$sites ->
+        [true] Lo que pasa con las imagenes NSFW en este servidor: { $policy }. Todo lo de sitios para adultos cuenta como NSFW
+       *[false] Lo que pasa con las imagenes NSFW en este servidor: { $policy }
+    }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-nsfw-noguild = Lo que pasa con las imagenes NSFW solo se puede cambiar dentro de un servidor { $sad-emoji }
+# $sad-emoji (String) - Sad emoji used when errors happen
+settings-nsfw-nopermission = Necesitás el permiso de Gestionar Servidor para cambiar que pasa con las imagenes NSFW { $sad-emoji }
+
+## Admin command
+admin = admin
+    .desc = Mantenimiento del bot, solo para sus administradores
+admin-purge-cache = borrar-cache
+    .desc = Olvida las busquedas de imagenes guardadas en cache
+admin-purge-cache-done = Olvide todas las busquedas en cache, las proximas van a los servicios de nuevo
+# $sad-emoji (String) - Sad emoji used when errors happen
+admin-nopermission = Solo los administradores del bot pueden hacer esto { $sad-emoji }
+
+## Draw command
+draw = dibujar
+    .desc = Dibuja la imagen pedida
+draw-options-list = pedido
+    .desc = ¿Qué querés que dibuje?
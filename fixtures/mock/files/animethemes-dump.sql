@@ -0,0 +1,85 @@
+-- Trimmed down AnimeThemes wiki dump, only the columns the bot reads
+SET NAMES utf8mb4;
+
+DROP TABLE IF EXISTS `anime`;
+CREATE TABLE `anime` (
+  `anime_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `slug` varchar(255) NOT NULL,
+  `name` varchar(255) NOT NULL,
+  PRIMARY KEY (`anime_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `anime` VALUES (1,'cowboy_bebop','Cowboy Bebop');
+
+DROP TABLE IF EXISTS `anime_synonyms`;
+CREATE TABLE `anime_synonyms` (
+  `synonym_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `anime_id` bigint unsigned NOT NULL,
+  `text` varchar(255) DEFAULT NULL,
+  PRIMARY KEY (`synonym_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `anime_synonyms` VALUES (1,1,'カウボーイビバップ');
+
+DROP TABLE IF EXISTS `songs`;
+CREATE TABLE `songs` (
+  `song_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `title` varchar(255) DEFAULT NULL,
+  PRIMARY KEY (`song_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `songs` VALUES (1,'Tank!'),(2,'The Real Folk Blues');
+
+DROP TABLE IF EXISTS `artists`;
+CREATE TABLE `artists` (
+  `artist_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `slug` varchar(255) NOT NULL,
+  `name` varchar(255) NOT NULL,
+  PRIMARY KEY (`artist_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `artists` VALUES (1,'the_seatbelts','The Seatbelts'),(2,'mai_yamane','Mai Yamane');
+
+DROP TABLE IF EXISTS `artist_song`;
+CREATE TABLE `artist_song` (
+  `artist_id` bigint unsigned NOT NULL,
+  `song_id` bigint unsigned NOT NULL,
+  `as` varchar(255) DEFAULT NULL,
+  PRIMARY KEY (`artist_id`,`song_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `artist_song` VALUES (1,1,NULL),(1,2,NULL),(2,2,NULL);
+
+DROP TABLE IF EXISTS `anime_themes`;
+CREATE TABLE `anime_themes` (
+  `theme_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `anime_id` bigint unsigned NOT NULL,
+  `song_id` bigint unsigned DEFAULT NULL,
+  `slug` varchar(255) NOT NULL,
+  PRIMARY KEY (`theme_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `anime_themes` VALUES (1,1,1,'OP1'),(2,1,2,'ED1');
+
+DROP TABLE IF EXISTS `anime_theme_entries`;
+CREATE TABLE `anime_theme_entries` (
+  `entry_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `theme_id` bigint unsigned NOT NULL,
+  PRIMARY KEY (`entry_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `anime_theme_entries` VALUES (1,1),(2,2);
+
+DROP TABLE IF EXISTS `videos`;
+CREATE TABLE `videos` (
+  `video_id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `basename` varchar(255) NOT NULL,
+  `resolution` int DEFAULT NULL,
+  `nc` tinyint(1) NOT NULL DEFAULT '0',
+  `subbed` tinyint(1) NOT NULL DEFAULT '0',
+  `lyrics` tinyint(1) NOT NULL DEFAULT '0',
+  `source` int DEFAULT NULL,
+  PRIMARY KEY (`video_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `videos` VALUES (1,'CowboyBebop-OP1.webm',1080,1,0,0,2),(2,'CowboyBebop-ED1.webm',1080,1,0,0,2);
+
+DROP TABLE IF EXISTS `anime_theme_entry_video`;
+CREATE TABLE `anime_theme_entry_video` (
+  `entry_id` bigint unsigned NOT NULL,
+  `video_id` bigint unsigned NOT NULL,
+  PRIMARY KEY (`entry_id`,`video_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+INSERT INTO `anime_theme_entry_video` VALUES (1,1),(2,2);
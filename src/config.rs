@@ -0,0 +1,281 @@
+use std::{collections::HashMap, env, fs, io::ErrorKind, net::SocketAddr, path::PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use toml::{Table, Value};
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+
+use crate::util::web::Service;
+
+/// Where the config is read from when `CONFIG_FILE` isn't set.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Environment variables that override a key of the config file.
+static ENV_OVERRIDES: &[(&str, &str, Kind)] = &[
+    ("DISCORD_TOKEN", "discord.token", Kind::String),
+    ("DEV_GUILD_ID", "discord.dev_guild", Kind::String),
+    ("SHARD_START", "gateway.shards.start", Kind::Integer),
+    ("SHARD_END", "gateway.shards.end", Kind::Integer),
+    ("SHARD_TOTAL", "gateway.shards.total", Kind::Integer),
+    ("DATABASE_URL", "database.url", Kind::String),
+    ("BONSAI_FILE", "database.bonsai_file", Kind::String),
+    ("SENTRY_DSN", "sentry.dsn", Kind::String),
+    ("HTTP_USER_AGENT", "http.user_agent", Kind::String),
+    ("SAUCENAO_TOKEN", "saucenao.token", Kind::String),
+    ("STABLE_DIFFUSION", "stable_diffusion.url", Kind::String),
+    ("SAUCENAO_URL", "services.saucenao", Kind::String),
+    ("TRACEMOE_URL", "services.tracemoe", Kind::String),
+    ("JISHO_URL", "services.jisho", Kind::String),
+    ("YANDEX_URL", "services.yandex", Kind::String),
+    ("ANIMETHEMES_URL", "services.animethemes", Kind::String),
+    ("IQDB_URL", "services.iqdb", Kind::String),
+    ("ASCII2D_URL", "services.ascii2d", Kind::String),
+    ("ANILIST_URL", "services.anilist", Kind::String),
+    ("MOCK_ADDR", "mock.addr", Kind::String),
+    ("MOCK_FIXTURES", "mock.fixtures", Kind::String),
+    ("METRICS_ADDR", "metrics.addr", Kind::String),
+    ("CACHE_TTL", "cache.ttl", Kind::Integer),
+    ("PSEUDO_LOCALE", "i18n.pseudo_locale", Kind::Boolean),
+];
+
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Integer,
+    Boolean,
+}
+
+/// Everything the bot reads at startup. Optional sections turn off the features that need them.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub discord: DiscordConfig,
+    #[serde(default)]
+    pub gateway: GatewayConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
+    /// Base URLs replacing the real ones, keyed by [`Service::name`]
+    #[serde(default)]
+    pub services: HashMap<String, String>,
+    /// Answers every service request with the fixtures instead
+    pub mock: Option<MockConfig>,
+    /// Serves the metrics for scraping
+    pub metrics: Option<MetricsConfig>,
+    pub sentry: Option<SentryConfig>,
+    pub saucenao: Option<SauceNaoConfig>,
+    pub stable_diffusion: Option<StableDiffusionConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscordConfig {
+    pub token: String,
+    /// Registers the commands only in this guild, which updates them instantly
+    pub dev_guild: Option<Id<GuildMarker>>,
+    /// Users allowed to use /admin, it isn't registered without any
+    #[serde(default)]
+    pub admins: Vec<Id<UserMarker>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GatewayConfig {
+    /// Shards this process runs, all the recommended ones if it isn't set
+    pub shards: Option<ShardRange>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct ShardRange {
+    pub start: u64,
+    pub end: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub bonsai_file: String,
+}
+
+/// Settings of the client used for every service that isn't Discord.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    /// Seconds a request (body included) can take, retries get their own time
+    pub timeout: u64,
+    pub connect_timeout: u64,
+    /// Extra attempts for 5xx, 429 and connection errors
+    pub retries: u32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            user_agent: concat!(
+                "sweetiebot/",
+                env!("CARGO_PKG_VERSION"),
+                " (+https://github.com/ImUrX/sweetiebot)"
+            )
+            .to_string(),
+            timeout: 20,
+            connect_timeout: 5,
+            retries: 2,
+        }
+    }
+}
+
+/// How the reverse image search results are kept around.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Seconds a result is kept, 0 turns the cache off
+    pub ttl: u64,
+    /// Also finds resized copies of an image by their perceptual hash
+    pub perceptual: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            ttl: 3 * 24 * 60 * 60,
+            perceptual: true,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Replies in accented en-US to spot the texts that skip the FTL files
+    pub pseudo_locale: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MockConfig {
+    #[serde(default = "default_mock_addr")]
+    pub addr: SocketAddr,
+    /// Folder with the JSON fixture files
+    pub fixtures: PathBuf,
+}
+
+fn default_mock_addr() -> SocketAddr {
+    ([127, 0, 0, 1], 8787).into()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsConfig {
+    pub addr: SocketAddr,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SentryConfig {
+    pub dsn: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SauceNaoConfig {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StableDiffusionConfig {
+    pub url: String,
+}
+
+impl Config {
+    /// Reads `CONFIG_FILE` (or `config.toml`), applies the environment overrides on top
+    /// and validates the result. The file is optional so the env alone can configure the bot.
+    pub fn load() -> Result<Self> {
+        let path = env::var("CONFIG_FILE").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string());
+        let mut table = match fs::read_to_string(&path) {
+            Ok(text) => {
+                toml::from_str::<Table>(&text).with_context(|| format!("Invalid {path}"))?
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => Table::new(),
+            Err(err) => return Err(err).with_context(|| format!("Couldn't read {path}")),
+        };
+
+        for (var, path, kind) in ENV_OVERRIDES {
+            if let Ok(value) = env::var(var) {
+                let value = match kind {
+                    Kind::String => Value::String(value),
+                    Kind::Integer => Value::Integer(
+                        value
+                            .parse()
+                            .with_context(|| format!("{var} has to be a number"))?,
+                    ),
+                    Kind::Boolean => Value::Boolean(match value.as_str() {
+                        "true" | "1" => true,
+                        "false" | "0" | "" => false,
+                        _ => bail!("{var} has to be true or false"),
+                    }),
+                };
+                set_key(&mut table, path, value)
+                    .with_context(|| format!("Couldn't apply {var}"))?;
+            }
+        }
+
+        let config: Config = Value::Table(table)
+            .try_into()
+            .context("Invalid configuration")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(!self.discord.token.is_empty(), "discord.token is empty");
+        ensure!(self.http.timeout > 0, "http.timeout can't be 0");
+        if let Some(ShardRange { start, end, total }) = self.gateway.shards {
+            ensure!(
+                start < end && end <= total,
+                "Shard range {start}..{end} doesn't fit in {total} shards"
+            );
+        }
+        if let Some(sentry) = &self.sentry {
+            sentry
+                .dsn
+                .parse::<sentry::types::Dsn>()
+                .context("Invalid sentry.dsn")?;
+        }
+        for (name, url) in &self.services {
+            ensure!(
+                Service::ALL
+                    .iter()
+                    .any(|x| x.name() == name && *x != Service::StableDiffusion),
+                "services.{name} isn't a service"
+            );
+            Url::parse(url).with_context(|| format!("Invalid services.{name}"))?;
+        }
+        if let Some(stable_diffusion) = &self.stable_diffusion {
+            Url::parse(&stable_diffusion.url).context("Invalid stable_diffusion.url")?;
+        }
+        if let Some(saucenao) = &self.saucenao {
+            ensure!(!saucenao.token.is_empty(), "saucenao.token is empty");
+        }
+        Ok(())
+    }
+}
+
+/// Sets a dotted `path` like `discord.token`, creating the tables in between.
+fn set_key(table: &mut Table, path: &str, value: Value) -> Result<()> {
+    let (parents, last) = path.rsplit_once('.').unwrap_or(("", path));
+    let mut table = table;
+    for key in parents.split('.').filter(|x| !x.is_empty()) {
+        let entry = table
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match entry {
+            Value::Table(table) => table,
+            _ => bail!("{key} isn't a table"),
+        };
+    }
+    table.insert(last.to_string(), value);
+    Ok(())
+}
@@ -9,9 +9,14 @@ use image::{
     RgbaImage,
 };
 use twilight_interactions::command::{AutocompleteValue, CommandModel, CreateCommand};
-use twilight_model::{application::interaction::Interaction, http::attachment::Attachment};
+use twilight_model::{
+    application::interaction::{application_command::CommandData, Interaction},
+    http::attachment::Attachment,
+};
 
 use crate::{
+    config::Config,
+    interaction::command::{CommandFuture, SlashCommand},
     util::{
         stablediffusion::{get_txt2img, Txt2Img},
         DEFERRED_RESPONSE,
@@ -32,6 +37,20 @@ pub struct DrawCommandAutocomplete {
     list: AutocompleteValue<String>,
 }
 
+impl SlashCommand for DrawCommand<'_> {
+    fn enabled(config: &Config) -> bool {
+        config.stable_diffusion.is_some()
+    }
+
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            DrawCommand::from_interaction(data.into())?
+                .run(info, interaction)
+                .await
+        })
+    }
+}
+
 static COORDINATES: &[(i64, i64)] = &[(0, 0), (256, 0), (0, 256), (256, 256)];
 
 impl DrawCommand<'_> {
@@ -46,7 +65,7 @@ impl DrawCommand<'_> {
             batch_size: 4,
             ..Default::default()
         };
-        let res = get_txt2img(&req).await?;
+        let res = get_txt2img(&info.web, &req).await?;
         if let Some(images) = res.images {
             let mut img = RgbaImage::new(512, 512);
 
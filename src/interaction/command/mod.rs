@@ -1,2 +1,231 @@
+use anyhow::{bail, Result};
+use futures::future::BoxFuture;
+use twilight_interactions::command::CreateCommand;
+use twilight_model::application::{
+    command::{Command, CommandType},
+    interaction::{
+        application_command::CommandData, message_component::MessageComponentInteractionData,
+        Interaction,
+    },
+};
+use twilight_util::builder::command::CommandBuilder;
+
+use crate::{config::Config, util::i18n::localize_command, ClusterData};
+
 pub mod creativity;
+pub mod utility;
 pub mod weeb;
+
+pub type CommandFuture<'a> = BoxFuture<'a, Result<()>>;
+
+/// A slash command that can be added to a [`CommandRegistry`].
+pub trait SlashCommand: CreateCommand {
+    /// Parses the interaction data and runs the command.
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_>;
+
+    /// Answers an autocomplete request, `None` if the command doesn't autocomplete.
+    fn autocomplete(
+        _info: ClusterData,
+        _interaction: &Interaction,
+        _data: CommandData,
+    ) -> Option<CommandFuture<'_>> {
+        None
+    }
+
+    /// Whether the command gets registered, for commands that need an optional config section.
+    fn enabled(_config: &Config) -> bool {
+        true
+    }
+
+    /// Handles a component whose custom id is prefixed by `{NAME}:`.
+    fn component<'a>(
+        _info: ClusterData,
+        _interaction: &'a Interaction,
+        _data: &'a MessageComponentInteractionData,
+    ) -> Option<CommandFuture<'a>> {
+        None
+    }
+}
+
+/// A command in the Apps menu of messages, it gets the message instead of any options.
+pub trait MessageCommand {
+    /// Shown as is in the menu, so it can have spaces and capitals
+    const NAME: &'static str;
+
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_>;
+
+    /// Whether the command gets registered, for commands that need an optional config section.
+    fn enabled(_config: &Config) -> bool {
+        true
+    }
+}
+
+type RunFn = for<'a> fn(ClusterData, &'a Interaction, CommandData) -> CommandFuture<'a>;
+type AutocompleteFn =
+    for<'a> fn(ClusterData, &'a Interaction, CommandData) -> Option<CommandFuture<'a>>;
+type ComponentFn = for<'a> fn(
+    ClusterData,
+    &'a Interaction,
+    &'a MessageComponentInteractionData,
+) -> Option<CommandFuture<'a>>;
+
+fn create<T: CreateCommand>() -> Command {
+    T::create_command().into()
+}
+
+fn create_message<T: MessageCommand>() -> Command {
+    CommandBuilder::new(T::NAME, "", CommandType::Message).build()
+}
+
+fn no_autocomplete(
+    _info: ClusterData,
+    _interaction: &Interaction,
+    _data: CommandData,
+) -> Option<CommandFuture<'_>> {
+    None
+}
+
+fn no_component<'a>(
+    _info: ClusterData,
+    _interaction: &'a Interaction,
+    _data: &'a MessageComponentInteractionData,
+) -> Option<CommandFuture<'a>> {
+    None
+}
+
+pub struct CommandHandler {
+    pub name: &'static str,
+    create: fn() -> Command,
+    enabled: fn(&Config) -> bool,
+    run: RunFn,
+    autocomplete: AutocompleteFn,
+    component: ComponentFn,
+}
+
+impl CommandHandler {
+    pub fn new<T: SlashCommand>() -> Self {
+        CommandHandler {
+            name: T::NAME,
+            create: create::<T>,
+            enabled: T::enabled,
+            run: T::run,
+            autocomplete: T::autocomplete,
+            component: T::component,
+        }
+    }
+
+    pub fn new_message<T: MessageCommand>() -> Self {
+        CommandHandler {
+            name: T::NAME,
+            create: create_message::<T>,
+            enabled: T::enabled,
+            run: T::run,
+            autocomplete: no_autocomplete,
+            component: no_component,
+        }
+    }
+
+    pub fn create_command(&self) -> Command {
+        (self.create)()
+    }
+
+    pub fn is_enabled(&self, config: &Config) -> bool {
+        (self.enabled)(config)
+    }
+
+    pub async fn run(
+        &self,
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Result<()> {
+        (self.run)(info, interaction, data).await
+    }
+
+    pub async fn autocomplete(
+        &self,
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Result<()> {
+        if let Some(future) = (self.autocomplete)(info, interaction, data) {
+            future.await
+        } else {
+            bail!("Unknown command autocomplete {}", self.name)
+        }
+    }
+
+    pub async fn component(
+        &self,
+        info: ClusterData,
+        interaction: &Interaction,
+        data: &MessageComponentInteractionData,
+    ) -> Result<()> {
+        if let Some(future) = (self.component)(info, interaction, data) {
+            future.await
+        } else {
+            bail!("Unknown command component {}", data.custom_id)
+        }
+    }
+}
+
+/// Every command the bot knows about, in the order they get registered to Discord.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<CommandHandler>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: SlashCommand>(mut self) -> Self {
+        assert!(
+            self.get(T::NAME).is_none(),
+            "Command {} was registered twice",
+            T::NAME
+        );
+        self.commands.push(CommandHandler::new::<T>());
+        self
+    }
+
+    pub fn register_message<T: MessageCommand>(mut self) -> Self {
+        assert!(
+            self.get(T::NAME).is_none(),
+            "Command {} was registered twice",
+            T::NAME
+        );
+        self.commands.push(CommandHandler::new_message::<T>());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CommandHandler> {
+        self.commands.iter().find(|x| x.name == name)
+    }
+
+    /// Looks up the command that owns a component by its `{NAME}:` custom id prefix.
+    pub fn get_by_custom_id(&self, custom_id: &str) -> Option<&CommandHandler> {
+        custom_id
+            .split_once(':')
+            .and_then(|(name, _)| self.get(name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CommandHandler> {
+        self.commands.iter()
+    }
+
+    /// Builds the commands enabled by `config` (all of them without one) with their
+    /// localizations, failing if one of the keys is missing.
+    pub fn create_commands(&self, config: Option<&Config>) -> Result<Vec<Command>> {
+        self.commands
+            .iter()
+            .filter(|x| config.map_or(true, |config| x.is_enabled(config)))
+            .map(|x| {
+                let mut command = x.create_command();
+                localize_command(&mut command)?;
+                Ok(command)
+            })
+            .collect()
+    }
+}
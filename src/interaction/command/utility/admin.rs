@@ -0,0 +1,95 @@
+use anyhow::Result;
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    application::interaction::{application_command::CommandData, Interaction},
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    config::Config,
+    interaction::command::{CommandFuture, SlashCommand},
+    util::{i18n::get_text, random_sad_emoji},
+    ClusterData,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "admin",
+    desc = "Maintenance of the bot, only for its admins",
+    default_permissions = "admin_permissions"
+)]
+pub enum AdminCommand {
+    #[command(name = "purge-cache")]
+    PurgeCache(AdminPurgeCache),
+}
+
+/// Hidden from everyone but the server admins, the bot admins are checked when it's used.
+fn admin_permissions() -> Permissions {
+    Permissions::ADMINISTRATOR
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "purge-cache",
+    desc = "Forgets the cached reverse image searches"
+)]
+pub struct AdminPurgeCache {}
+
+impl SlashCommand for AdminCommand {
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            let lang = info.get_lang(interaction).await?;
+            let is_admin = interaction
+                .author_id()
+                .map_or(false, |id| info.config.discord.admins.contains(&id));
+            if !is_admin {
+                return reply(
+                    &info,
+                    interaction,
+                    get_text(
+                        lang,
+                        "admin-nopermission",
+                        &[("sad-emoji", random_sad_emoji().into())],
+                    ),
+                )
+                .await;
+            }
+
+            match AdminCommand::from_interaction(data.into())? {
+                AdminCommand::PurgeCache(_) => {
+                    info.sauce_cache.purge().await?;
+                    reply(
+                        &info,
+                        interaction,
+                        get_text(lang, "admin-purge-cache-done", &[]),
+                    )
+                    .await
+                }
+            }
+        })
+    }
+
+    fn enabled(config: &Config) -> bool {
+        !config.discord.admins.is_empty()
+    }
+}
+
+async fn reply(info: &ClusterData, interaction: &Interaction, content: String) -> Result<()> {
+    let response = InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(
+            InteractionResponseDataBuilder::new()
+                .content(content)
+                .flags(MessageFlags::EPHEMERAL)
+                .build(),
+        ),
+    };
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &response)
+        .await?;
+    Ok(())
+}
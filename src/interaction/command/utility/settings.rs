@@ -0,0 +1,344 @@
+use anyhow::{Context, Result};
+use twilight_interactions::command::{CommandModel, CreateCommand};
+use twilight_model::{
+    application::{
+        command::{CommandOptionChoice, CommandOptionChoiceValue},
+        interaction::{
+            application_command::{CommandData, CommandDataOption, CommandOptionValue},
+            Interaction,
+        },
+    },
+    channel::message::MessageFlags,
+    guild::Permissions,
+    http::interaction::{InteractionResponse, InteractionResponseType},
+    id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+    interaction::command::{CommandFuture, SlashCommand},
+    util::{
+        censor::{Censor, CensorMethod, NsfwPolicy, DEFAULT_STRENGTH},
+        i18n::{get_text, AVAILABLE_LANGS},
+        random_sad_emoji,
+        settings::{GuildSettings, UserSettings},
+    },
+    ClusterData,
+};
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "settings", desc = "Changes how the bot behaves")]
+pub enum SettingsCommand {
+    #[command(name = "language")]
+    Language(SettingsLanguage),
+    #[command(name = "censor")]
+    Censor(SettingsCensor),
+    #[command(name = "nsfw")]
+    Nsfw(SettingsNsfw),
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(name = "language", desc = "Changes the language the bot replies in")]
+pub struct SettingsLanguage {
+    #[command(
+        desc = "Language to use, leave it empty to go back to the default one",
+        autocomplete = true
+    )]
+    language: Option<String>,
+    #[command(desc = "Changes the default language of the whole server instead of yours")]
+    server: Option<bool>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "censor",
+    desc = "Changes how NSFW images get censored outside NSFW channels in the server"
+)]
+pub struct SettingsCensor {
+    #[command(desc = "How to censor them, leave it empty to pixelate them like by default")]
+    method: Option<CensorMethod>,
+    #[command(
+        desc = "How little of the image can be made out, 50 by default",
+        min_value = 1,
+        max_value = 100
+    )]
+    strength: Option<i64>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "nsfw",
+    desc = "Changes what happens to NSFW images outside NSFW channels in the server"
+)]
+pub struct SettingsNsfw {
+    #[command(desc = "What to do with them, leave it empty to censor them like by default")]
+    policy: Option<NsfwPolicy>,
+    #[command(desc = "Treats everything from adult sites as NSFW, even if it's rated safe")]
+    sites: Option<bool>,
+}
+
+impl SlashCommand for SettingsCommand {
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            match SettingsCommand::from_interaction(data.into())? {
+                SettingsCommand::Language(language) => language.run(info, interaction).await,
+                SettingsCommand::Censor(censor) => censor.run(info, interaction).await,
+                SettingsCommand::Nsfw(nsfw) => nsfw.run(info, interaction).await,
+            }
+        })
+    }
+
+    fn autocomplete(
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Option<CommandFuture<'_>> {
+        Some(Box::pin(async move {
+            let input = focused_value(&data.options)
+                .unwrap_or_default()
+                .to_lowercase();
+            let choices = AVAILABLE_LANGS
+                .entries()
+                .map(|(code, id)| (code, get_text(id, "lang-name", &[])))
+                .filter(|(code, name)| {
+                    code.to_lowercase().contains(&input) || name.to_lowercase().contains(&input)
+                })
+                .map(|(code, name)| CommandOptionChoice {
+                    name: format!("{name} ({code})"),
+                    name_localizations: None,
+                    value: CommandOptionChoiceValue::String(code.to_string()),
+                })
+                .collect::<Vec<_>>();
+
+            let response = InteractionResponse {
+                kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .choices(choices)
+                        .build(),
+                ),
+            };
+            info.http
+                .interaction(info.application_id)
+                .create_response(interaction.id, &interaction.token, &response)
+                .await?;
+            Ok(())
+        }))
+    }
+}
+
+fn focused_value(options: &[CommandDataOption]) -> Option<&str> {
+    options.iter().find_map(|option| match &option.value {
+        CommandOptionValue::Focused(value, _) => Some(value.as_str()),
+        CommandOptionValue::SubCommand(options) | CommandOptionValue::SubCommandGroup(options) => {
+            focused_value(options)
+        }
+        _ => None,
+    })
+}
+
+impl SettingsLanguage {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let lang = info.get_lang(interaction).await?;
+        if let Some(code) = &self.language {
+            if !AVAILABLE_LANGS.contains_key(code.as_str()) {
+                return reply(
+                    &info,
+                    interaction,
+                    get_text(
+                        lang,
+                        "settings-language-invalid",
+                        &[
+                            ("lang", code.as_str().into()),
+                            ("sad-emoji", random_sad_emoji().into()),
+                        ],
+                    ),
+                )
+                .await;
+            }
+        }
+
+        let scope = if self.server.unwrap_or(false) {
+            let guild_id = if let Some(guild_id) = interaction.guild_id {
+                guild_id
+            } else {
+                return reply(
+                    &info,
+                    interaction,
+                    get_text(
+                        lang,
+                        "settings-language-noguild",
+                        &[("sad-emoji", random_sad_emoji().into())],
+                    ),
+                )
+                .await;
+            };
+            if !can_manage_guild(interaction) {
+                return reply(
+                    &info,
+                    interaction,
+                    get_text(
+                        lang,
+                        "settings-language-nopermission",
+                        &[("sad-emoji", random_sad_emoji().into())],
+                    ),
+                )
+                .await;
+            }
+
+            let mut settings = GuildSettings::get(&info.bonsai, guild_id).await?;
+            settings.lang = self.language.clone();
+            settings.save(&info.bonsai, guild_id).await?;
+            "server"
+        } else {
+            let user_id = interaction
+                .author_id()
+                .context("Interaction without author")?;
+            let mut settings = UserSettings::get(&info.bonsai, user_id).await?;
+            settings.lang = self.language.clone();
+            settings.save(&info.bonsai, user_id).await?;
+            "user"
+        };
+
+        // Answer in the language that ended up being used
+        let lang = info.get_lang(interaction).await?;
+        let new_lang = self
+            .language
+            .as_deref()
+            .and_then(|code| AVAILABLE_LANGS.get(code).copied());
+        let content = if let Some(new_lang) = new_lang {
+            get_text(
+                lang,
+                "settings-language-updated",
+                &[
+                    ("scope", scope.into()),
+                    ("lang", get_text(new_lang, "lang-name", &[]).into()),
+                ],
+            )
+        } else {
+            get_text(lang, "settings-language-reset", &[("scope", scope.into())])
+        };
+        reply(&info, interaction, content).await
+    }
+}
+
+impl SettingsCensor {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let lang = info.get_lang(interaction).await?;
+        let guild_id = match managed_guild(interaction) {
+            Ok(guild_id) => guild_id,
+            Err(problem) => {
+                let key = format!("settings-censor-{problem}");
+                let content = get_text(lang, &key, &[("sad-emoji", random_sad_emoji().into())]);
+                return reply(&info, interaction, content).await;
+            }
+        };
+
+        let strength = match self.strength {
+            Some(strength) => u8::try_from(strength)
+                .with_context(|| format!("{strength} isn't a valid censor strength"))?,
+            None => DEFAULT_STRENGTH,
+        };
+        let censor = Censor::new(self.method.unwrap_or_default(), strength)?;
+        let mut settings = GuildSettings::get(&info.bonsai, guild_id).await?;
+        settings.censor_method = self.method;
+        settings.censor_strength = self.strength.map(|_| censor.strength);
+        settings.save(&info.bonsai, guild_id).await?;
+
+        let method = get_text(
+            lang,
+            &format!("settings-options-method-{}", censor.method.name()),
+            &[],
+        );
+        reply(
+            &info,
+            interaction,
+            get_text(
+                lang,
+                "settings-censor-updated",
+                &[
+                    ("method", method.into()),
+                    ("strength", censor.strength.into()),
+                ],
+            ),
+        )
+        .await
+    }
+}
+
+impl SettingsNsfw {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let lang = info.get_lang(interaction).await?;
+        let guild_id = match managed_guild(interaction) {
+            Ok(guild_id) => guild_id,
+            Err(problem) => {
+                let key = format!("settings-nsfw-{problem}");
+                let content = get_text(lang, &key, &[("sad-emoji", random_sad_emoji().into())]);
+                return reply(&info, interaction, content).await;
+            }
+        };
+
+        let mut settings = GuildSettings::get(&info.bonsai, guild_id).await?;
+        settings.nsfw_policy = self.policy;
+        settings.nsfw_sites = self.sites;
+        settings.save(&info.bonsai, guild_id).await?;
+
+        let censor = settings.censor();
+        let policy = get_text(
+            lang,
+            &format!("settings-options-policy-{}", censor.policy.name()),
+            &[],
+        );
+        reply(
+            &info,
+            interaction,
+            get_text(
+                lang,
+                "settings-nsfw-updated",
+                &[
+                    ("policy", policy.into()),
+                    ("sites", censor.nsfw_sites.to_string().into()),
+                ],
+            ),
+        )
+        .await
+    }
+}
+
+/// The guild whose settings can be changed, or the end of the key explaining why they can't.
+fn managed_guild(interaction: &Interaction) -> std::result::Result<Id<GuildMarker>, &'static str> {
+    let guild_id = interaction.guild_id.ok_or("noguild")?;
+    if can_manage_guild(interaction) {
+        Ok(guild_id)
+    } else {
+        Err("nopermission")
+    }
+}
+
+/// Only those who can manage the server change its settings.
+fn can_manage_guild(interaction: &Interaction) -> bool {
+    interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .map_or(false, |permissions| {
+            permissions.contains(Permissions::MANAGE_GUILD)
+        })
+}
+
+async fn reply(info: &ClusterData, interaction: &Interaction, content: String) -> Result<()> {
+    let response = InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(
+            InteractionResponseDataBuilder::new()
+                .content(content)
+                .flags(MessageFlags::EPHEMERAL)
+                .build(),
+        ),
+    };
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &response)
+        .await?;
+    Ok(())
+}
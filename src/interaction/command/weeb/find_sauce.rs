@@ -0,0 +1,223 @@
+use std::{slice, time::Duration};
+
+use anyhow::Result;
+use futures::StreamExt;
+use reqwest::Url;
+use tokio::{
+    select,
+    time::{timeout_at, Instant},
+};
+use twilight_model::{
+    application::interaction::{application_command::CommandData, Interaction, InteractionData},
+    channel::message::{
+        component::{
+            ActionRow, Button, ButtonStyle, Component, SelectMenu, SelectMenuOption, SelectMenuType,
+        },
+        MessageFlags,
+    },
+    http::interaction::{InteractionResponse, InteractionResponseType},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+use unic_langid::LanguageIdentifier;
+
+use crate::{
+    interaction::command::{CommandFuture, MessageCommand},
+    util::{
+        find_images, i18n::get_text, random_sad_emoji, tracemoe::SearchOptions, ImageOrigin,
+        SauceImage, DEFERRED_COMPONENT_RESPONSE,
+    },
+    ClusterData,
+};
+
+use super::sauce::{search_saucenao, search_tracemoe};
+
+/// How long the picker waits for the user to choose.
+const PICKER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Searches the source of an image in a message, so it doesn't have to be uploaded again
+/// to use /sauce.
+pub struct FindSauceCommand;
+
+impl MessageCommand for FindSauceCommand {
+    const NAME: &'static str = "Find sauce";
+
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            let lang = info.get_lang(interaction).await?;
+            let message = data.target_id.and_then(|id| {
+                data.resolved
+                    .as_ref()
+                    .and_then(|resolved| resolved.messages.get(&id.cast()))
+            });
+            let mut images = message.map(find_images).unwrap_or_default();
+            // Links can point anywhere, the ones into the bot's network aren't offered
+            images.retain(|(origin, image)| {
+                *origin != ImageOrigin::Link
+                    || Url::parse(&image.url).map_or(false, |x| info.web.is_public_url(&x))
+            });
+            if images.is_empty() {
+                let response = InteractionResponse {
+                    kind: InteractionResponseType::ChannelMessageWithSource,
+                    data: Some(
+                        InteractionResponseDataBuilder::new()
+                            .content(get_text(
+                                lang,
+                                "find-sauce-noimages",
+                                &[("sad-emoji", random_sad_emoji().into())],
+                            ))
+                            .flags(MessageFlags::EPHEMERAL)
+                            .build(),
+                    ),
+                };
+                info.http
+                    .interaction(interaction.application_id)
+                    .create_response(interaction.id, &interaction.token, &response)
+                    .await?;
+                return Ok(());
+            }
+
+            let response = InteractionResponse {
+                kind: InteractionResponseType::ChannelMessageWithSource,
+                data: Some(
+                    InteractionResponseDataBuilder::new()
+                        .content(get_text(lang, "find-sauce-pick", &[]))
+                        .components(picker(lang, &images))
+                        .flags(MessageFlags::EPHEMERAL)
+                        .build(),
+                ),
+            };
+            info.http
+                .interaction(interaction.application_id)
+                .create_response(interaction.id, &interaction.token, &response)
+                .await?;
+            let message_id = info
+                .http
+                .interaction(interaction.application_id)
+                .response(&interaction.token)
+                .await?
+                .model()
+                .await?
+                .id;
+
+            let mut components =
+                info.standby
+                    .wait_for_component_stream(message_id, |event: &Interaction| {
+                        if let Some(InteractionData::MessageComponent(data)) = &event.data {
+                            ["image", "saucenao", "tracemoe"].contains(&data.custom_id.as_str())
+                        } else {
+                            false
+                        }
+                    });
+            let deadline = Instant::now() + PICKER_TIMEOUT;
+            let mut selected = 0;
+            let chosen = loop {
+                let component = select! {
+                    next = timeout_at(deadline, components.next()) => match next {
+                        Ok(Some(component)) => component,
+                        _ => break None,
+                    },
+                    _ = info.shutdown.cancelled() => break None,
+                };
+                let custom_id = match &component.data {
+                    Some(InteractionData::MessageComponent(data)) => {
+                        if data.custom_id == "image" {
+                            selected = data
+                                .values
+                                .first()
+                                .and_then(|x| x.parse().ok())
+                                .filter(|x| *x < images.len())
+                                .unwrap_or(0);
+                        }
+                        data.custom_id.clone()
+                    }
+                    _ => continue,
+                };
+                if custom_id == "image" {
+                    info.http
+                        .interaction(interaction.application_id)
+                        .create_response(
+                            component.id,
+                            &component.token,
+                            &DEFERRED_COMPONENT_RESPONSE,
+                        )
+                        .await?;
+                } else {
+                    break Some((custom_id, component));
+                }
+            };
+
+            // The picker only gets used once
+            info.http
+                .interaction(interaction.application_id)
+                .update_response(&interaction.token)
+                .components(Some(&[]))?
+                .await?;
+
+            if let Some((engine, component)) = chosen {
+                let image = slice::from_ref(&images[selected].1);
+                if engine == "saucenao" {
+                    search_saucenao(info, &component, image, None, None, None).await?;
+                } else {
+                    let options = SearchOptions::default();
+                    search_tracemoe(info, &component, image, &options, false).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// A menu to choose the image when there's more than one and a button per search engine.
+fn picker(lang: &LanguageIdentifier, images: &[(ImageOrigin, SauceImage)]) -> Vec<Component> {
+    let mut rows = Vec::new();
+    if images.len() > 1 {
+        let options = images
+            .iter()
+            .enumerate()
+            .map(|(index, (origin, image))| SelectMenuOption {
+                default: index == 0,
+                description: Some(get_text(
+                    lang,
+                    "find-sauce-origin",
+                    &[("origin", origin.name().into())],
+                )),
+                emoji: None,
+                // Labels can't go over 100 characters
+                label: format!(
+                    "{}. {}",
+                    index + 1,
+                    image.file_name().chars().take(90).collect::<String>()
+                ),
+                value: index.to_string(),
+            })
+            .collect();
+        rows.push(Component::ActionRow(ActionRow {
+            components: vec![Component::SelectMenu(SelectMenu {
+                channel_types: None,
+                custom_id: "image".to_string(),
+                disabled: false,
+                kind: SelectMenuType::Text,
+                max_values: Some(1),
+                min_values: Some(1),
+                options: Some(options),
+                placeholder: None,
+            })],
+        }));
+    }
+    rows.push(Component::ActionRow(ActionRow {
+        components: [("saucenao", "SauceNAO"), ("tracemoe", "trace.moe")]
+            .into_iter()
+            .map(|(custom_id, label)| {
+                Component::Button(Button {
+                    custom_id: Some(custom_id.to_string()),
+                    label: Some(label.to_string()),
+                    style: ButtonStyle::Primary,
+                    disabled: false,
+                    emoji: None,
+                    url: None,
+                })
+            })
+            .collect(),
+    }));
+    rows
+}
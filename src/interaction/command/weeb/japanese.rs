@@ -12,7 +12,7 @@ use twilight_interactions::command::{AutocompleteValue, CommandModel, CreateComm
 use twilight_model::{
     application::{
         command::{CommandOptionChoice, CommandOptionChoiceValue},
-        interaction::Interaction,
+        interaction::{application_command::CommandData, Interaction},
     },
     channel::message::embed::EmbedField,
     http::{
@@ -25,11 +25,18 @@ use twilight_util::builder::{
     InteractionResponseDataBuilder,
 };
 use twilight_validate::embed::FIELD_VALUE_LENGTH;
+use unic_langid::LanguageIdentifier;
 use urlencoding::encode;
 use wana_kana::{ConvertJapanese, IsJapaneseStr};
 
 use crate::{
-    util::{measure_text_width, EmbedList, DEFERRED_RESPONSE},
+    interaction::command::{CommandFuture, SlashCommand},
+    util::{
+        i18n::get_text,
+        measure_text_width,
+        web::{Service, WebClient},
+        EmbedList, DEFERRED_RESPONSE,
+    },
     ClusterData,
 };
 
@@ -49,6 +56,28 @@ pub struct JishoCommandAutocomplete {
     word: AutocompleteValue<String>,
 }
 
+impl SlashCommand for JishoCommand<'_> {
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            JishoCommand::from_interaction(data.into())?
+                .run(info, interaction)
+                .await
+        })
+    }
+
+    fn autocomplete(
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Option<CommandFuture<'_>> {
+        Some(Box::pin(async move {
+            JishoCommandAutocomplete::from_interaction(data.into())?
+                .run(info, interaction)
+                .await
+        }))
+    }
+}
+
 impl JishoCommandAutocomplete {
     pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
         let mut vec = Vec::new();
@@ -95,14 +124,17 @@ impl JishoCommand<'_> {
             .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
             .await?;
 
-        let res = jisho_words(&self.word).await?;
+        let res = jisho_words(&info.web, &self.word).await?;
+        let lang = info.get_lang(interaction).await?;
         let mut embed_list = EmbedList::new(
             info.http.clone(),
             interaction.application_id,
             info.standby.clone(),
+            lang,
+            info.shutdown.clone(),
         );
         for data in res.data.iter().take(12) {
-            let (embed, attachment) = Self::make_embed(data)?;
+            let (embed, attachment) = Self::make_embed(data, lang)?;
             embed_list.add(embed.build(), Some(attachment));
         }
         embed_list
@@ -111,14 +143,17 @@ impl JishoCommand<'_> {
         Ok(())
     }
 
-    pub fn make_embed(word: &JishoWord) -> Result<(EmbedBuilder, Attachment)> {
+    pub fn make_embed(
+        word: &JishoWord,
+        lang: &LanguageIdentifier,
+    ) -> Result<(EmbedBuilder, Attachment)> {
         let mut embed = EmbedBuilder::new()
             .title(word.slug.clone())
             .color(0x56_D9_26)
             .url(format!("https://jisho.org/word/{}", word.slug))
             .thumbnail(ImageSource::attachment("furigana.png")?);
 
-        let tags = Self::process_tags(word).join(" - ");
+        let tags = Self::process_tags(word, lang).join(" - ");
         if !tags.is_empty() {
             embed = embed.description(format!("**{}**", tags))
         }
@@ -136,7 +171,7 @@ impl JishoCommand<'_> {
             content += &sense
                 .restrictions
                 .iter()
-                .map(|x| format!("Only applies to {}", x))
+                .map(|x| get_text(lang, "japanese-restriction", &[("word", x.as_str().into())]))
                 .join(", ");
             content += &sense
                 .see_also
@@ -187,7 +222,9 @@ impl JishoCommand<'_> {
                 })
                 .join("、");
 
-            fields.push(EmbedFieldBuilder::new("Other forms", forms).build());
+            fields.push(
+                EmbedFieldBuilder::new(get_text(lang, "japanese-other-forms", &[]), forms).build(),
+            );
         }
 
         for field in fields {
@@ -204,14 +241,18 @@ impl JishoCommand<'_> {
         ))
     }
 
-    fn process_tags(word: &JishoWord) -> Vec<String> {
+    fn process_tags(word: &JishoWord, lang: &LanguageIdentifier) -> Vec<String> {
         let mut vec = Vec::new();
         if word.is_common.unwrap_or(false) {
-            vec.push("common word".to_owned());
+            vec.push(get_text(lang, "japanese-common", &[]));
         }
         for tag in word.tags.iter() {
             if let Some(lvl) = tag.strip_prefix("wanikani") {
-                vec.push(format!("wanikani lvl{}", lvl));
+                vec.push(get_text(
+                    lang,
+                    "japanese-wanikani",
+                    &[("level", lvl.into())],
+                ));
             } else {
                 eprintln!("discovered new tag {}", tag);
             }
@@ -317,19 +358,14 @@ impl JishoCommand<'_> {
     }
 }
 
-pub async fn jisho_words(keyword: &str) -> Result<JishoResult> {
-    let client = reqwest::Client::new();
-    let mut res = client
-        .get("https://jisho.org/api/v1/search/words")
-        .query(&[("keyword", keyword)])
-        .send()
-        .await?
-        .json::<JishoResult>()
-        .await?;
+pub async fn jisho_words(web: &WebClient, keyword: &str) -> Result<JishoResult> {
+    let request = web
+        .get(web.url(Service::Jisho, "/api/v1/search/words")?)
+        .query(&[("keyword", keyword)]);
+    let mut res = web.send(request).await?.json::<JishoResult>().await?;
 
-    let html = client
-        .get(format!("https://jisho.org/search/{}", encode(keyword)))
-        .send()
+    let html = web
+        .send(web.get(web.url(Service::Jisho, &format!("/search/{}", encode(keyword)))?))
         .await?
         .text()
         .await?;
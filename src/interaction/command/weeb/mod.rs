@@ -1,3 +1,4 @@
+pub mod find_sauce;
 pub mod japanese;
 pub mod op;
 pub mod sauce;
@@ -1,22 +1,30 @@
 use std::borrow::Cow;
 
 use anyhow::Result;
-use rand::{seq::SliceRandom, thread_rng};
 use twilight_interactions::command::{AutocompleteValue, CommandModel, CreateCommand};
 use twilight_model::{
     application::{
         command::{CommandOptionChoice, CommandOptionChoiceValue},
-        interaction::Interaction,
+        interaction::{
+            application_command::CommandData, message_component::MessageComponentInteractionData,
+            Interaction,
+        },
+    },
+    channel::message::{
+        component::{Button, ButtonStyle},
+        MessageFlags,
     },
-    channel::message::MessageFlags,
     http::interaction::{InteractionResponse, InteractionResponseType},
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
+use unic_langid::LanguageIdentifier;
 
 use crate::{
+    interaction::command::{CommandFuture, SlashCommand},
     util::{
         animethemes::{get_video, search_theme},
-        SAD_EMOJIS,
+        i18n::get_text,
+        random_sad_emoji,
     },
     ClusterData,
 };
@@ -34,6 +42,57 @@ pub struct OpeningCommandAutocomplete {
     theme: AutocompleteValue<String>,
 }
 
+impl SlashCommand for OpeningCommand<'_> {
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            OpeningCommand::from_interaction(data.into())?
+                .run(info, interaction)
+                .await
+        })
+    }
+
+    fn autocomplete(
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Option<CommandFuture<'_>> {
+        Some(Box::pin(async move {
+            OpeningCommandAutocomplete::from_interaction(data.into())?
+                .run(info, interaction)
+                .await
+        }))
+    }
+
+    fn component<'a>(
+        info: ClusterData,
+        interaction: &'a Interaction,
+        data: &'a MessageComponentInteractionData,
+    ) -> Option<CommandFuture<'a>> {
+        let anime = data.custom_id.strip_prefix("op:")?;
+        Some(Box::pin(async move {
+            OpeningCommand {
+                theme: anime.into(),
+            }
+            .run(info, interaction)
+            .await
+        }))
+    }
+}
+
+/// A button that looks up the themes of `anime` like `/op` does, for other commands to link
+/// their results to it.
+pub fn lookup_button(lang: &LanguageIdentifier, anime: &str) -> Button {
+    Button {
+        // Custom ids can't go over 100 characters
+        custom_id: Some(format!("op:{}", anime.chars().take(97).collect::<String>())),
+        label: Some(get_text(lang, "op-lookup", &[])),
+        style: ButtonStyle::Secondary,
+        disabled: false,
+        emoji: None,
+        url: None,
+    }
+}
+
 impl OpeningCommandAutocomplete {
     pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
         let vec = if let AutocompleteValue::Focused(input) = &self.theme {
@@ -64,6 +123,7 @@ impl OpeningCommandAutocomplete {
 
 impl OpeningCommand<'_> {
     pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let lang = info.get_lang(interaction).await?;
         let theme_id = if self.theme.starts_with('\0') {
             self.theme[1..]
                 .parse::<u64>()
@@ -77,9 +137,10 @@ impl OpeningCommand<'_> {
                     kind: InteractionResponseType::ChannelMessageWithSource,
                     data: Some(
                         InteractionResponseDataBuilder::new()
-                            .content(format!(
-                                "Couldn't find the anime theme {}\nHint: Use the suggestions that pop up while you write so you can search the precise theme you are searching for.",
-                                SAD_EMOJIS.choose(&mut thread_rng()).unwrap()
+                            .content(get_text(
+                                lang,
+                                "op-notfound",
+                                &[("sad-emoji", random_sad_emoji().into())],
                             ))
                             .flags(MessageFlags::EPHEMERAL)
                             .build(),
@@ -101,9 +162,10 @@ impl OpeningCommand<'_> {
                 kind: InteractionResponseType::ChannelMessageWithSource,
                 data: Some(
                     InteractionResponseDataBuilder::new()
-                        .content(format!(
-                            "This theme is yet to be uploaded. {}",
-                            SAD_EMOJIS.choose(&mut thread_rng()).unwrap()
+                        .content(get_text(
+                            lang,
+                            "op-notuploaded",
+                            &[("sad-emoji", random_sad_emoji().into())],
                         ))
                         .flags(MessageFlags::EPHEMERAL)
                         .build(),
@@ -118,14 +180,6 @@ impl OpeningCommand<'_> {
             return Ok(());
         }
 
-        let mut prelude = "".to_string();
-        if let Some(title) = &videos[0].title {
-            prelude += &format!("**{title}**");
-        }
-        if let Some(name) = &videos[0].artist_name {
-            prelude += &format!(" from {name}");
-        }
-
         let tags = {
             let tags = videos[0].get_tag();
             if tags.is_empty() {
@@ -139,9 +193,24 @@ impl OpeningCommand<'_> {
             kind: InteractionResponseType::ChannelMessageWithSource,
             data: Some(
                 InteractionResponseDataBuilder::new()
-                    .content(format!(
-                        "{prelude}\nhttps://animethemes.moe/anime/{}/{}{tags}",
-                        videos[0].anime_slug, videos[0].theme_slug
+                    .content(get_text(
+                        lang,
+                        "op-message-prelude",
+                        &[
+                            ("title", videos[0].title.as_deref().unwrap_or("none").into()),
+                            (
+                                "artist",
+                                videos[0].artist_name.as_deref().unwrap_or("none").into(),
+                            ),
+                            (
+                                "link",
+                                format!(
+                                    "https://animethemes.moe/anime/{}/{}{tags}",
+                                    videos[0].anime_slug, videos[0].theme_slug
+                                )
+                                .into(),
+                            ),
+                        ],
                     ))
                     .build(),
             ),
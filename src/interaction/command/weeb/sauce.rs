@@ -1,124 +1,807 @@
-use anyhow::Result;
-use rand::{seq::SliceRandom, thread_rng};
-use twilight_interactions::command::{CommandModel, CreateCommand};
-use twilight_model::{application::interaction::Interaction, channel::Attachment};
-use twilight_util::builder::InteractionResponseDataBuilder;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use reqwest::Url;
+use twilight_interactions::command::{AutocompleteValue, CommandModel, CreateCommand};
+use twilight_model::{
+    application::{
+        command::{CommandOptionChoice, CommandOptionChoiceValue},
+        interaction::{application_command::CommandData, Interaction},
+    },
+    channel::{
+        message::{component::Button, embed::Embed, MessageFlags},
+        Attachment,
+    },
+    http::{
+        attachment::Attachment as HttpAttachment,
+        interaction::{InteractionResponse, InteractionResponseType},
+    },
+};
+use twilight_util::builder::{embed::EmbedBuilder, InteractionResponseDataBuilder};
+use unic_langid::LanguageIdentifier;
 
 use crate::{
+    interaction::command::{CommandFuture, SlashCommand},
     util::{
-        saucenao::{build_embed as build_saucenao_embed, fetch as fetch_saucenao},
-        tracemoe::{build_embed as build_tracemoe_embed, fetch as fetch_tracemoe},
-        EmbedList, DEFERRED_RESPONSE, SAD_EMOJIS,
+        anilist::search_anime,
+        ascii2d::{build_embed as build_ascii2d_embed, fetch as fetch_ascii2d},
+        i18n::get_text,
+        iqdb::{build_embed as build_iqdb_embed, fetch as fetch_iqdb},
+        random_sad_emoji,
+        sauce::{search as search_all, SauceNaoSearch},
+        saucenao::{
+            build_embed as build_saucenao_embed, fetch as fetch_saucenao, Database, SearchOptions,
+            MAX_RESULTS, RATE_LIMITED_STATUS,
+        },
+        tracemoe::{
+            build_embed as build_tracemoe_embed, fetch as fetch_tracemoe, quota as tracemoe_quota,
+            SearchOptions as TraceOptions,
+        },
+        web::{TooLarge, MAX_DOWNLOAD_SIZE},
+        EmbedList, SauceImage, DEFERRED_RESPONSE,
     },
     ClusterData,
 };
 
+use super::op::lookup_button;
+
 #[derive(CommandModel, CreateCommand)]
 #[command(name = "sauce", desc = "Searches the image's original source")]
 pub enum SauceCommand {
+    #[command(name = "auto")]
+    Auto(SauceAuto),
     #[command(name = "saucenao")]
     SauceNAO(SauceSauceNAO),
     #[command(name = "tracemoe")]
     TraceMoe(SauceTraceMoe),
+    #[command(name = "iqdb")]
+    Iqdb(SauceIqdb),
+    #[command(name = "ascii2d")]
+    Ascii2d(SauceAscii2d),
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "auto",
+    desc = "Searches the image's source with every engine at once"
+)]
+pub struct SauceAuto {
+    #[command(desc = "Image to reverse-lookup for")]
+    image: Option<Attachment>,
+    #[command(desc = "Link to an image to reverse-lookup for")]
+    url: Option<String>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image2: Option<Attachment>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image3: Option<Attachment>,
 }
 
 #[derive(CommandModel, CreateCommand)]
 #[command(name = "saucenao", desc = "Searches the image's source with SauceNAO")]
 pub struct SauceSauceNAO {
     #[command(desc = "Image to reverse-lookup for")]
-    image: Attachment,
+    image: Option<Attachment>,
+    #[command(desc = "Link to an image to reverse-lookup for")]
+    url: Option<String>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image2: Option<Attachment>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image3: Option<Attachment>,
+    #[command(desc = "Sites to search in, all of them by default")]
+    database: Option<Database>,
+    #[command(
+        desc = "How many results to show, 5 by default",
+        min_value = 1,
+        max_value = 10
+    )]
+    results: Option<i64>,
+    #[command(
+        desc = "Leaves out the results less similar than this percentage",
+        min_value = 0.0,
+        max_value = 100.0
+    )]
+    similarity: Option<f64>,
 }
 
 #[derive(CommandModel, CreateCommand)]
 #[command(name = "tracemoe", desc = "Searches the image's source with trace.moe")]
 pub struct SauceTraceMoe {
     #[command(desc = "Image to reverse-lookup for")]
-    image: Attachment,
+    image: Option<Attachment>,
+    #[command(desc = "Link to an image to reverse-lookup for")]
+    url: Option<String>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image2: Option<Attachment>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image3: Option<Attachment>,
+    #[command(desc = "Attaches a short clip of the scene instead of a still")]
+    clip: Option<bool>,
+    #[command(desc = "Removes the black borders of the image before searching")]
+    cut_borders: Option<bool>,
+    #[command(desc = "Only looks for the scene in this anime", autocomplete = true)]
+    anime: Option<String>,
 }
 
-impl SauceTraceMoe {
+#[derive(CommandModel)]
+pub enum SauceCommandAutocomplete {
+    #[command(name = "tracemoe")]
+    TraceMoe(SauceTraceMoeAutocomplete),
+}
+
+#[derive(CommandModel)]
+#[command(autocomplete = true)]
+pub struct SauceTraceMoeAutocomplete {
+    anime: AutocompleteValue<String>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "iqdb",
+    desc = "Searches the image's source in boorus with IQDB"
+)]
+pub struct SauceIqdb {
+    #[command(desc = "Image to reverse-lookup for")]
+    image: Option<Attachment>,
+    #[command(desc = "Link to an image to reverse-lookup for")]
+    url: Option<String>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image2: Option<Attachment>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image3: Option<Attachment>,
+}
+
+#[derive(CommandModel, CreateCommand)]
+#[command(
+    name = "ascii2d",
+    desc = "Searches the image's source in art sites with ascii2d"
+)]
+pub struct SauceAscii2d {
+    #[command(desc = "Image to reverse-lookup for")]
+    image: Option<Attachment>,
+    #[command(desc = "Link to an image to reverse-lookup for")]
+    url: Option<String>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image2: Option<Attachment>,
+    #[command(desc = "Another image to reverse-lookup for")]
+    image3: Option<Attachment>,
+}
+
+/// The embeds found for an image with their buttons, or what to say when there aren't any.
+type Page = std::result::Result<Vec<(Embed, Option<HttpAttachment>, Vec<Button>)>, String>;
+
+impl SlashCommand for SauceCommand {
+    fn run(info: ClusterData, interaction: &Interaction, data: CommandData) -> CommandFuture<'_> {
+        Box::pin(async move {
+            match SauceCommand::from_interaction(data.into())? {
+                SauceCommand::Auto(auto) => auto.run(info, interaction).await,
+                SauceCommand::SauceNAO(saucenao) => saucenao.run(info, interaction).await,
+                SauceCommand::TraceMoe(trace) => trace.run(info, interaction).await,
+                SauceCommand::Iqdb(iqdb) => iqdb.run(info, interaction).await,
+                SauceCommand::Ascii2d(ascii2d) => ascii2d.run(info, interaction).await,
+            }
+        })
+    }
+
+    fn autocomplete(
+        info: ClusterData,
+        interaction: &Interaction,
+        data: CommandData,
+    ) -> Option<CommandFuture<'_>> {
+        Some(Box::pin(async move {
+            match SauceCommandAutocomplete::from_interaction(data.into())? {
+                SauceCommandAutocomplete::TraceMoe(trace) => trace.run(info, interaction).await,
+            }
+        }))
+    }
+}
+
+impl SauceTraceMoeAutocomplete {
+    /// Suggests anime from AniList, picking one sends its id after a null character.
     pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let vec = match &self.anime {
+            AutocompleteValue::Focused(input) if !input.trim().is_empty() => {
+                search_anime(&info.web, input.trim(), 25)
+                    .await?
+                    .iter()
+                    .map(|anime| CommandOptionChoice {
+                        // Names can't go over 100 characters
+                        name: anime.name().chars().take(100).collect(),
+                        name_localizations: None,
+                        value: CommandOptionChoiceValue::String(format!("\0{}", anime.id)),
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let response = InteractionResponse {
+            kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+            data: Some(InteractionResponseDataBuilder::new().choices(vec).build()),
+        };
         info.http
-            .interaction(interaction.application_id)
-            .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+            .interaction(info.application_id)
+            .create_response(interaction.id, &interaction.token, &response)
             .await?;
 
-        let res = fetch_tracemoe(&self.image).await?;
-        let mut embed_list = EmbedList::new(
-            info.http.clone(),
-            interaction.application_id,
-            info.standby.clone(),
-        );
+        Ok(())
+    }
+}
 
-        let nsfw = info.is_nsfw_interaction(interaction).await?;
-        for data in res.result.iter().take(10) {
-            let (embed, attachment) = build_tracemoe_embed(data, nsfw).await?;
-            embed_list.add(embed.build(), Some(attachment));
+impl SauceAuto {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let attachments = [&self.image, &self.image2, &self.image3];
+        match collect_images(&info, attachments, self.url.as_deref()) {
+            Ok(images) => search_auto(info, interaction, &images).await,
+            Err(key) => reply_error(&info, interaction, key).await,
         }
-        embed_list
-            .defer_reply(interaction, InteractionResponseDataBuilder::new())
-            .await?;
-        Ok(())
     }
 }
 
-impl SauceSauceNAO {
+/// Answers with what every engine finds merged together, SauceNAO is left out if it isn't
+/// set up.
+async fn search_auto(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+) -> Result<()> {
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+        .await?;
+
+    let lang = info.get_lang(interaction).await?;
+    let nsfw = info.is_nsfw_interaction(interaction).await?;
+    let censor = info.get_censor(interaction).await?;
+    let options = SearchOptions::new(None, None, None, nsfw, censor)?;
+    let mut pages = Vec::new();
+    for image in images {
+        let saucenao = info
+            .config
+            .saucenao
+            .as_ref()
+            .map(|saucenao| SauceNaoSearch {
+                limits: &info.saucenao_limits,
+                token: &saucenao.token,
+                options: &options,
+            });
+        let matches = match search_all(
+            &info.web,
+            &info.sauce_cache,
+            saucenao,
+            image,
+            nsfw,
+            censor,
+            lang,
+        )
+        .await
+        {
+            Ok(matches) => matches,
+            Err(err) if err.is::<TooLarge>() => {
+                pages.push(Err(too_large_text(lang)));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if matches.is_empty() {
+            pages.push(Err(get_text(
+                lang,
+                "sauce-auto-empty",
+                &[("sad-emoji", random_sad_emoji().into())],
+            )));
+            continue;
+        }
+        pages.push(Ok(matches
+            .into_iter()
+            .map(|(embed, attachment)| (embed, attachment, Vec::new()))
+            .collect()));
+    }
+    send_pages(&info, interaction, lang, images, pages).await
+}
+
+impl SauceTraceMoe {
     pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let attachments = [&self.image, &self.image2, &self.image3];
+        let images = match collect_images(&info, attachments, self.url.as_deref()) {
+            Ok(images) => images,
+            Err(key) => return reply_error(&info, interaction, key).await,
+        };
+        // Deferred before asking AniList, it can take longer than Discord waits for an answer
         info.http
             .interaction(interaction.application_id)
             .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
             .await?;
 
-        let res = fetch_saucenao(&self.image).await?;
+        // A suggestion has the id already, otherwise the best match is used
+        let anilist_id = match self.anime.as_deref().map(str::trim) {
+            None | Some("") => None,
+            Some(anime) => match anime.strip_prefix('\0') {
+                Some(id) => Some(
+                    id.parse()
+                        .context("Not an AniList id after null character")?,
+                ),
+                None => match search_anime(&info.web, anime, 1).await?.first() {
+                    Some(found) => Some(found.id),
+                    None => {
+                        return update_error(&info, interaction, "sauce-tracemoe-noanime").await
+                    }
+                },
+            },
+        };
+        let options = TraceOptions {
+            cut_borders: self.cut_borders.unwrap_or(false),
+            anilist_id,
+        };
+        send_tracemoe(
+            info,
+            interaction,
+            &images,
+            &options,
+            self.clip.unwrap_or(false),
+        )
+        .await
+    }
+}
 
-        if res.header.status > 0 && res.results.is_empty() {
-            info.http
-            .interaction(interaction.application_id)
-            .update_response(&interaction.token)
-            .content(
-                Some(
-                    &format!(
-                        "Seems that SauceNAO is not having a good time currently {} ``(status code: {})``",
-                        SAD_EMOJIS.choose(&mut thread_rng()).unwrap(),
-                        res.header.status
-                    )
-                )
-            )?
-            .await?;
-            return Ok(());
+/// Answers with the scenes trace.moe finds, the Find sauce menu uses it too with the
+/// default options.
+pub async fn search_tracemoe(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+    options: &TraceOptions,
+    clip: bool,
+) -> Result<()> {
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+        .await?;
+    send_tracemoe(info, interaction, images, options, clip).await
+}
+
+/// What [`search_tracemoe`] does once the interaction is deferred.
+async fn send_tracemoe(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+    options: &TraceOptions,
+    clip: bool,
+) -> Result<()> {
+    let lang = info.get_lang(interaction).await?;
+    let nsfw = info.is_nsfw_interaction(interaction).await?;
+    let censor = info.get_censor(interaction).await?;
+    let mut pages = Vec::new();
+    for image in images {
+        let mut res = match fetch_tracemoe(&info.web, &info.sauce_cache, image, options).await {
+            Ok(res) => res,
+            Err(err) if err.is::<TooLarge>() => {
+                pages.push(Err(too_large_text(lang)));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if !res.error.is_empty() {
+            pages.push(Err(get_text(
+                lang,
+                "sauce-tracemoe-error",
+                &[
+                    ("sad-emoji", random_sad_emoji().into()),
+                    ("error", res.error.clone().into()),
+                ],
+            )));
+            continue;
+        }
+        if res.result.is_empty() {
+            pages.push(Err(get_text(
+                lang,
+                "sauce-tracemoe-empty",
+                &[("sad-emoji", random_sad_emoji().into())],
+            )));
+            continue;
+        }
+        // Cached searches don't spend the quota, so it's only asked after real ones
+        if !res.cached {
+            res.quota = match tracemoe_quota(&info.web).await {
+                Ok(quota) => Some(quota),
+                Err(err) => {
+                    eprintln!("Couldn't get the trace.moe quota: {err}");
+                    None
+                }
+            };
+        }
+        let mut embeds = Vec::new();
+        for data in res.result.iter().take(10) {
+            let (embed, attachment) =
+                build_tracemoe_embed(&info.web, data, nsfw, censor, &res, clip, lang).await?;
+            let op = lookup_button(lang, &data.anilist.title.romaji);
+            embeds.push((embed.build(), attachment, vec![op]));
+        }
+        pages.push(Ok(embeds));
+    }
+    send_pages(&info, interaction, lang, images, pages).await
+}
+
+impl SauceIqdb {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let attachments = [&self.image, &self.image2, &self.image3];
+        match collect_images(&info, attachments, self.url.as_deref()) {
+            Ok(images) => search_iqdb(info, interaction, &images).await,
+            Err(key) => reply_error(&info, interaction, key).await,
+        }
+    }
+}
+
+/// Answers with the posts in boorus IQDB finds.
+async fn search_iqdb(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+) -> Result<()> {
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+        .await?;
+
+    let lang = info.get_lang(interaction).await?;
+    let nsfw = info.is_nsfw_interaction(interaction).await?;
+    let censor = info.get_censor(interaction).await?;
+    let mut pages = Vec::new();
+    for image in images {
+        let res = match fetch_iqdb(&info.web, &info.sauce_cache, image).await {
+            Ok(res) => res,
+            Err(err) if err.is::<TooLarge>() => {
+                pages.push(Err(too_large_text(lang)));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if res.matches.is_empty() {
+            pages.push(Err(get_text(
+                lang,
+                "sauce-iqdb-empty",
+                &[("sad-emoji", random_sad_emoji().into())],
+            )));
+            continue;
         }
+        let mut embeds = Vec::new();
+        for data in res.matches.iter() {
+            let (embed, attachment) =
+                build_iqdb_embed(&info.web, data, nsfw, censor, res.cached, lang).await?;
+            embeds.push((embed.build(), attachment, Vec::new()));
+        }
+        pages.push(Ok(embeds));
+    }
+    send_pages(&info, interaction, lang, images, pages).await
+}
 
-        if res.header.status < 0 {
-            info.http
-                .interaction(interaction.application_id)
-                .update_response(&interaction.token)
-                .content(
-                    Some(
-                        &format!(
-                            "Sorry, an error happened {} ``(status code: {})``\nDid you send a valid image?",
-                            SAD_EMOJIS.choose(&mut thread_rng()).unwrap(),
-                            res.header.status
-                        )
-                    )
-                )?
-                .await?;
-            return Ok(());
+impl SauceAscii2d {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let attachments = [&self.image, &self.image2, &self.image3];
+        match collect_images(&info, attachments, self.url.as_deref()) {
+            Ok(images) => search_ascii2d(info, interaction, &images).await,
+            Err(key) => reply_error(&info, interaction, key).await,
         }
+    }
+}
 
-        let mut embed_list = EmbedList::new(
-            info.http.clone(),
-            interaction.application_id,
-            info.standby.clone(),
-        );
+/// Answers with the posts in art sites ascii2d finds.
+async fn search_ascii2d(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+) -> Result<()> {
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+        .await?;
 
-        let nsfw = info.is_nsfw_interaction(interaction).await?;
-        for data in res.results.iter().take(10) {
-            let (embed, attachment) = build_saucenao_embed(data, nsfw).await?;
-            embed_list.add(embed.build(), Some(attachment));
+    let lang = info.get_lang(interaction).await?;
+    let nsfw = info.is_nsfw_interaction(interaction).await?;
+    let censor = info.get_censor(interaction).await?;
+    let mut pages = Vec::new();
+    for image in images {
+        let res = match fetch_ascii2d(&info.web, &info.sauce_cache, image).await {
+            Ok(res) => res,
+            Err(err) if err.is::<TooLarge>() => {
+                pages.push(Err(too_large_text(lang)));
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if res.matches.is_empty() {
+            pages.push(Err(get_text(
+                lang,
+                "sauce-ascii2d-empty",
+                &[("sad-emoji", random_sad_emoji().into())],
+            )));
+            continue;
         }
-        embed_list
-            .defer_reply(interaction, InteractionResponseDataBuilder::new())
+        let mut embeds = Vec::new();
+        for data in res.matches.iter().take(MAX_RESULTS.into()) {
+            let (embed, attachment) =
+                build_ascii2d_embed(&info.web, data, nsfw, censor, res.cached, lang).await?;
+            embeds.push((embed.build(), attachment, Vec::new()));
+        }
+        pages.push(Ok(embeds));
+    }
+    send_pages(&info, interaction, lang, images, pages).await
+}
+
+impl SauceSauceNAO {
+    pub async fn run(self, info: ClusterData, interaction: &Interaction) -> Result<()> {
+        let attachments = [&self.image, &self.image2, &self.image3];
+        match collect_images(&info, attachments, self.url.as_deref()) {
+            Ok(images) => {
+                search_saucenao(
+                    info,
+                    interaction,
+                    &images,
+                    self.database,
+                    self.results,
+                    self.similarity,
+                )
+                .await
+            }
+            Err(key) => reply_error(&info, interaction, key).await,
+        }
+    }
+}
+
+/// Answers with the results SauceNAO finds, the Find sauce menu uses it too with the
+/// default options.
+pub async fn search_saucenao(
+    info: ClusterData,
+    interaction: &Interaction,
+    images: &[SauceImage],
+    database: Option<Database>,
+    results: Option<i64>,
+    similarity: Option<f64>,
+) -> Result<()> {
+    let token = if let Some(saucenao) = &info.config.saucenao {
+        &saucenao.token
+    } else {
+        return reply_error(&info, interaction, "sauce-saucenao-disabled").await;
+    };
+
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &DEFERRED_RESPONSE)
+        .await?;
+
+    let lang = info.get_lang(interaction).await?;
+    let nsfw = info.is_nsfw_interaction(interaction).await?;
+    let censor = info.get_censor(interaction).await?;
+    let options = SearchOptions::new(database, results, similarity, nsfw, censor)?;
+    let mut pages = Vec::new();
+    for image in images {
+        pages.push(saucenao_page(&info, lang, image, token, &options).await?);
+    }
+    send_pages(&info, interaction, lang, images, pages).await
+}
+
+async fn saucenao_page(
+    info: &ClusterData,
+    lang: &LanguageIdentifier,
+    image: &SauceImage,
+    token: &str,
+    options: &SearchOptions,
+) -> Result<Page> {
+    let res = match fetch_saucenao(
+        &info.web,
+        &info.saucenao_limits,
+        &info.sauce_cache,
+        image,
+        token,
+        options,
+    )
+    .await
+    {
+        Ok(res) => res,
+        Err(err) if err.is::<TooLarge>() => return Ok(Err(too_large_text(lang))),
+        Err(err) => return Err(err),
+    };
+
+    if res.header.status == RATE_LIMITED_STATUS {
+        let wait = info.saucenao_limits.retry_after().unwrap_or_default();
+        return Ok(Err(rate_limited_text(lang, wait)?));
+    }
+
+    if res.header.status > 0 && res.results.is_empty() {
+        return Ok(Err(get_text(
+            lang,
+            "sauce-saucenao-unavailable",
+            &[
+                ("sad-emoji", random_sad_emoji().into()),
+                ("status", res.header.status.into()),
+            ],
+        )));
+    }
+
+    if res.header.status < 0 {
+        return Ok(Err(get_text(
+            lang,
+            "sauce-saucenao-error",
+            &[
+                ("sad-emoji", random_sad_emoji().into()),
+                ("status", res.header.status.into()),
+            ],
+        )));
+    }
+
+    if res.results.is_empty() {
+        let content = if options.min_similarity > 0.0 {
+            get_text(
+                lang,
+                "sauce-saucenao-nomatch",
+                &[
+                    ("sad-emoji", random_sad_emoji().into()),
+                    ("amount", options.min_similarity.to_string().into()),
+                ],
+            )
+        } else {
+            get_text(
+                lang,
+                "sauce-saucenao-empty",
+                &[("sad-emoji", random_sad_emoji().into())],
+            )
+        };
+        return Ok(Err(content));
+    }
+
+    let mut embeds = Vec::new();
+    for data in res.results.iter() {
+        let (embed, attachment) =
+            build_saucenao_embed(&info.web, data, options, res.cached, lang).await?;
+        embeds.push((embed.build(), attachment, Vec::new()));
+    }
+    Ok(Ok(embeds))
+}
+
+/// The attachments in order and then the link, or the message to answer with when there's
+/// nothing to search or the link can't be used.
+fn collect_images(
+    info: &ClusterData,
+    attachments: [&Option<Attachment>; 3],
+    url: Option<&str>,
+) -> std::result::Result<Vec<SauceImage>, &'static str> {
+    let mut images: Vec<SauceImage> = attachments
+        .into_iter()
+        .flatten()
+        .map(SauceImage::from)
+        .collect();
+    if let Some(url) = url {
+        let url = Url::parse(url.trim())
+            .ok()
+            .filter(|x| info.web.is_public_url(x))
+            .ok_or("sauce-badurl")?;
+        // Links without an extension are left for the services to figure out
+        let image = SauceImage::from_url(url.as_str()).unwrap_or(SauceImage {
+            url: url.into(),
+            content_type: None,
+            linked: true,
+        });
+        images.push(SauceImage {
+            linked: true,
+            ..image
+        });
+    }
+    if images.is_empty() {
+        return Err("sauce-noimage");
+    }
+    Ok(images)
+}
+
+/// Sends the pages of every image, with a label telling them apart when there's more than
+/// one. A lone image without results just gets its message.
+async fn send_pages(
+    info: &ClusterData,
+    interaction: &Interaction,
+    lang: &'static LanguageIdentifier,
+    images: &[SauceImage],
+    pages: Vec<Page>,
+) -> Result<()> {
+    if let [Err(content)] = pages.as_slice() {
+        info.http
+            .interaction(interaction.application_id)
+            .update_response(&interaction.token)
+            .content(Some(content.as_str()))?
             .await?;
-        Ok(())
+        return Ok(());
     }
+
+    let mut embed_list = EmbedList::new(
+        info.http.clone(),
+        interaction.application_id,
+        info.standby.clone(),
+        lang,
+        info.shutdown.clone(),
+    );
+    for (index, (image, page)) in images.iter().zip(pages).enumerate() {
+        let label = (images.len() > 1).then(|| {
+            get_text(
+                lang,
+                "sauce-image-label",
+                &[
+                    ("num", (index + 1).into()),
+                    ("total", images.len().into()),
+                    ("name", image.file_name().into()),
+                ],
+            )
+        });
+        match page {
+            Ok(embeds) => {
+                for (embed, attachment, buttons) in embeds {
+                    embed_list.add_with_buttons(embed, attachment, label.clone(), buttons);
+                }
+            }
+            // So the results of the other images can still be seen
+            Err(content) => {
+                let embed = EmbedBuilder::new().description(content).build();
+                embed_list.add_labeled(embed, None, label);
+            }
+        }
+    }
+    embed_list
+        .defer_reply(interaction, InteractionResponseDataBuilder::new())
+        .await?;
+    Ok(())
+}
+
+/// Answers only to the user with the message in `key`.
+async fn reply_error(info: &ClusterData, interaction: &Interaction, key: &str) -> Result<()> {
+    let lang = info.get_lang(interaction).await?;
+    let response = InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(
+            InteractionResponseDataBuilder::new()
+                .content(get_text(
+                    lang,
+                    key,
+                    &[("sad-emoji", random_sad_emoji().into())],
+                ))
+                .flags(MessageFlags::EPHEMERAL)
+                .build(),
+        ),
+    };
+    info.http
+        .interaction(interaction.application_id)
+        .create_response(interaction.id, &interaction.token, &response)
+        .await?;
+    Ok(())
+}
+
+/// Like [`reply_error`] for interactions that were deferred already.
+async fn update_error(info: &ClusterData, interaction: &Interaction, key: &str) -> Result<()> {
+    let lang = info.get_lang(interaction).await?;
+    let content = get_text(lang, key, &[("sad-emoji", random_sad_emoji().into())]);
+    info.http
+        .interaction(interaction.application_id)
+        .update_response(&interaction.token)
+        .content(Some(&content))?
+        .await?;
+    Ok(())
+}
+
+fn too_large_text(lang: &LanguageIdentifier) -> String {
+    get_text(
+        lang,
+        "sauce-toolarge",
+        &[
+            ("sad-emoji", random_sad_emoji().into()),
+            ("size", (MAX_DOWNLOAD_SIZE / 1024 / 1024).into()),
+        ],
+    )
+}
+
+/// Tells the user SauceNAO ran out of searches, with a timestamp Discord shows as
+/// "in 20 seconds" and such.
+fn rate_limited_text(lang: &LanguageIdentifier, wait: Duration) -> Result<String> {
+    let retry = (SystemTime::now() + wait).duration_since(UNIX_EPOCH)?;
+    Ok(get_text(
+        lang,
+        "sauce-saucenao-unavailable.ratelimited",
+        &[
+            ("sad-emoji", random_sad_emoji().into()),
+            ("retry", format!("<t:{}:R>", retry.as_secs()).into()),
+        ],
+    ))
 }
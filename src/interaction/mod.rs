@@ -1,38 +1,46 @@
 use anyhow::{bail, Result};
 
 use once_cell::sync::Lazy;
-use rand::{seq::SliceRandom, thread_rng};
 use sentry::integrations::anyhow::capture_anyhow;
 use twilight_gateway::ShardId;
-use twilight_interactions::command::{CommandModel, CreateCommand};
 use twilight_model::{
-    application::{
-        command::Command,
-        interaction::{InteractionData, InteractionType},
-    },
+    application::interaction::{InteractionData, InteractionType},
     channel::message::MessageFlags,
     gateway::payload::incoming::InteractionCreate,
     http::interaction::{InteractionResponse, InteractionResponseType},
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::{interaction::command::creativity::draw::DrawCommand, util::SAD_EMOJIS, ClusterData};
+use crate::{
+    util::{
+        i18n::{get_interaction_lang, get_text},
+        random_sad_emoji,
+    },
+    ClusterData,
+};
 
-use self::command::weeb::{
-    japanese::{JishoCommand, JishoCommandAutocomplete},
-    op::{OpeningCommand, OpeningCommandAutocomplete},
-    sauce::SauceCommand,
+use self::command::{
+    creativity::draw::DrawCommand,
+    utility::{admin::AdminCommand, settings::SettingsCommand},
+    weeb::{
+        find_sauce::FindSauceCommand, japanese::JishoCommand, op::OpeningCommand,
+        sauce::SauceCommand,
+    },
+    CommandRegistry,
 };
 
 pub mod command;
+pub mod sync;
 
-pub static CREATE_COMMANDS: Lazy<Vec<Command>> = Lazy::new(|| {
-    vec![
-        //DrawCommand::create_command().into(),
-        JishoCommand::create_command().into(),
-        OpeningCommand::create_command().into(),
-        SauceCommand::create_command().into(),
-    ]
+pub static COMMANDS: Lazy<CommandRegistry> = Lazy::new(|| {
+    CommandRegistry::new()
+        .register::<AdminCommand>()
+        .register::<DrawCommand>()
+        .register::<JishoCommand>()
+        .register::<OpeningCommand>()
+        .register::<SauceCommand>()
+        .register_message::<FindSauceCommand>()
+        .register::<SettingsCommand>()
 });
 
 pub async fn handle_interaction(
@@ -40,49 +48,37 @@ pub async fn handle_interaction(
     interaction: Box<InteractionCreate>,
     info: ClusterData,
 ) -> Result<()> {
-    if let Some(data) = &interaction.0.data {
-        match data {
-            InteractionData::ApplicationCommand(cmd) => match interaction.0.kind {
+    match &interaction.0.data {
+        Some(InteractionData::ApplicationCommand(cmd)) => {
+            let handler = if let Some(handler) = COMMANDS.get(&cmd.name) {
+                handler
+            } else {
+                bail!("Unknown command interaction {}", cmd.name)
+            };
+            // It may still be registered from a run that had it enabled
+            if !handler.is_enabled(&info.config) {
+                bail!("Disabled command interaction {}", cmd.name)
+            }
+
+            match interaction.0.kind {
                 InteractionType::ApplicationCommand => {
-                    let command: Result<()> = try {
-                        let info = info.clone();
-                        match cmd.name.as_str() {
-                            "japanese" => {
-                                JishoCommand::from_interaction((**cmd).clone().into())?
-                                    .run(info, &interaction.0)
-                                    .await?
-                            }
-                            "op" => {
-                                OpeningCommand::from_interaction((**cmd).clone().into())?
-                                    .run(info, &interaction.0)
-                                    .await?
-                            }
-                            "sauce" => {
-                                let sauce = SauceCommand::from_interaction((**cmd).clone().into())?;
-                                match sauce {
-                                    SauceCommand::SauceNAO(saucenao) => {
-                                        saucenao.run(info, &interaction.0).await?
-                                    }
-                                    SauceCommand::TraceMoe(trace) => {
-                                        trace.run(info, &interaction.0).await?
-                                    }
-                                }
-                            }
-                            "draw" => {
-                                DrawCommand::from_interaction((**cmd).clone().into())?
-                                    .run(info, &interaction.0)
-                                    .await?
-                            }
-                            _ => bail!("Unknown command interaction {}", cmd.name),
-                        };
-                    };
+                    let command = handler
+                        .run(info.clone(), &interaction.0, (**cmd).clone())
+                        .await;
 
                     if let Err(e) = &command {
                         capture_anyhow(e);
-                        let err_string = format!(
-                            "An error occurred, it has been reported and will be fixed soon {}\n```\n{}\n```",
-                            SAD_EMOJIS.choose(&mut thread_rng()).unwrap(),
-                            e
+                        let lang = info
+                            .get_lang(&interaction.0)
+                            .await
+                            .unwrap_or_else(|_| get_interaction_lang(&interaction.0));
+                        let err_string = get_text(
+                            lang,
+                            "error-generic",
+                            &[
+                                ("sad-emoji", random_sad_emoji().into()),
+                                ("error", e.to_string().into()),
+                            ],
                         );
                         let client = info.http.interaction(info.application_id);
                         let msg_error = client
@@ -125,28 +121,20 @@ pub async fn handle_interaction(
                     command?
                 }
                 InteractionType::ApplicationCommandAutocomplete => {
-                    match cmd.name.as_str() {
-                        "japanese" => {
-                            JishoCommandAutocomplete::from_interaction((**cmd).clone().into())?
-                                .run(info, &interaction.0)
-                                .await?
-                        }
-                        "op" => {
-                            OpeningCommandAutocomplete::from_interaction((**cmd).clone().into())?
-                                .run(info, &interaction.0)
-                                .await?
-                        }
-                        _ => bail!("Unknown command autocomplete {}", cmd.name),
-                    };
+                    handler
+                        .autocomplete(info, &interaction.0, (**cmd).clone())
+                        .await?
                 }
                 _ => {}
-            },
-            _ => {}
+            }
         }
-    } else {
-        match interaction.0.kind {
-            _ => {}
+        // Components without a command prefix (like the EmbedList buttons) are handled by standby
+        Some(InteractionData::MessageComponent(component)) => {
+            if let Some(handler) = COMMANDS.get_by_custom_id(&component.custom_id) {
+                handler.component(info, &interaction.0, component).await?
+            }
         }
+        _ => {}
     }
 
     Ok(())
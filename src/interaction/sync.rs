@@ -0,0 +1,256 @@
+use std::{collections::HashMap, fmt};
+
+use anyhow::{bail, Context, Result};
+use twilight_http::client::InteractionClient;
+use twilight_model::{
+    application::command::{
+        Command, CommandOption, CommandOptionType, CommandOptionValue, CommandType,
+    },
+    id::{marker::GuildMarker, Id},
+};
+
+use crate::ClusterData;
+
+/// Names of the commands that have to change for Discord to match ours.
+#[derive(Debug, Default)]
+pub struct CommandDiff {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+impl CommandDiff {
+    pub fn new(live: &[Command], wanted: &[Command]) -> Self {
+        let mut diff = Self::default();
+        for command in wanted {
+            match live.iter().find(|x| x.name == command.name) {
+                None => diff.created.push(command.name.clone()),
+                Some(old) if is_outdated(old, command) => diff.updated.push(command.name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.deleted = live
+            .iter()
+            .filter(|x| !wanted.iter().any(|y| y.name == x.name))
+            .map(|x| x.name.clone())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl fmt::Display for CommandDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            ("created", &self.created),
+            ("updated", &self.updated),
+            ("deleted", &self.deleted),
+        ];
+        let mut first = true;
+        for (action, names) in parts.into_iter().filter(|(_, x)| !x.is_empty()) {
+            if !first {
+                f.write_str(", ")?;
+            }
+            first = false;
+            write!(f, "{action} {}", names.join(" "))?;
+        }
+        if first {
+            f.write_str("no changes")?;
+        }
+        Ok(())
+    }
+}
+
+fn localizations(map: &Option<HashMap<String, String>>) -> Option<&HashMap<String, String>> {
+    map.as_ref().filter(|x| !x.is_empty())
+}
+
+/// Discord leaves out whatever is false or empty and sends whole numbers without decimals,
+/// so both sides go through this before comparing their options.
+fn normalize_options(options: &[CommandOption]) -> Vec<CommandOption> {
+    options
+        .iter()
+        .cloned()
+        .map(|mut option| {
+            option.autocomplete = option.autocomplete.filter(|x| *x);
+            option.required = option.required.filter(|x| *x);
+            option.channel_types = option.channel_types.filter(|x| !x.is_empty());
+            option.name_localizations = option.name_localizations.filter(|x| !x.is_empty());
+            option.description_localizations =
+                option.description_localizations.filter(|x| !x.is_empty());
+            option.choices = option.choices.filter(|x| !x.is_empty()).map(|choices| {
+                choices
+                    .into_iter()
+                    .map(|mut choice| {
+                        choice.name_localizations =
+                            choice.name_localizations.filter(|x| !x.is_empty());
+                        choice
+                    })
+                    .collect()
+            });
+            if option.kind == CommandOptionType::Number {
+                option.min_value = option.min_value.map(as_number);
+                option.max_value = option.max_value.map(as_number);
+            }
+            option.options = option
+                .options
+                .filter(|x| !x.is_empty())
+                .map(|x| normalize_options(&x));
+            option
+        })
+        .collect()
+}
+
+fn as_number(value: CommandOptionValue) -> CommandOptionValue {
+    match value {
+        CommandOptionValue::Integer(x) => CommandOptionValue::Number(x as f64),
+        x => x,
+    }
+}
+
+/// Compares the fields we set ourselves, Discord fills in the rest (ids, version, etc).
+#[allow(deprecated)]
+fn is_outdated(live: &Command, wanted: &Command) -> bool {
+    live.kind != wanted.kind
+        || live.description != wanted.description
+        || localizations(&live.name_localizations) != localizations(&wanted.name_localizations)
+        || localizations(&live.description_localizations)
+            != localizations(&wanted.description_localizations)
+        || live.default_member_permissions != wanted.default_member_permissions
+        // Guild commands don't have a dm_permission
+        || (live.guild_id.is_none()
+            && live.dm_permission.unwrap_or(true) != wanted.dm_permission.unwrap_or(true))
+        || live.nsfw.unwrap_or(false) != wanted.nsfw.unwrap_or(false)
+        || normalize_options(&live.options) != normalize_options(&wanted.options)
+}
+
+/// Registers `commands` globally, or only in `guild_id` which updates instantly and is
+/// meant for development.
+///
+/// Only the commands in the returned diff are sent, one request each. Updates go through
+/// the create endpoints too since Discord overwrites the command with the same name in
+/// place (keeping its id), while the update ones can't change the localizations.
+pub async fn sync_commands(
+    info: &ClusterData,
+    guild_id: Option<Id<GuildMarker>>,
+    commands: &[Command],
+) -> Result<CommandDiff> {
+    let client = info.http.interaction(info.application_id);
+    let live = if let Some(guild_id) = guild_id {
+        client
+            .guild_commands(guild_id)
+            .with_localizations(true)
+            .await?
+            .model()
+            .await?
+    } else {
+        client
+            .global_commands()
+            .with_localizations(true)
+            .await?
+            .model()
+            .await?
+    };
+
+    let diff = CommandDiff::new(&live, commands);
+    for name in diff.created.iter().chain(&diff.updated) {
+        // Every created or updated name comes from `commands`
+        let Some(command) = commands.iter().find(|x| &x.name == name) else {
+            continue;
+        };
+        create_command(&client, guild_id, command)
+            .await
+            .with_context(|| format!("Couldn't register /{name}"))?;
+    }
+    for name in &diff.deleted {
+        let command_id = live
+            .iter()
+            .find(|x| &x.name == name)
+            .and_then(|x| x.id)
+            .with_context(|| format!("Live command {name} without an id"))?;
+        if let Some(guild_id) = guild_id {
+            client.delete_guild_command(guild_id, command_id).await?;
+        } else {
+            client.delete_global_command(command_id).await?;
+        }
+    }
+    Ok(diff)
+}
+
+/// Creates `command`, or overwrites the one with the same name.
+#[allow(deprecated)]
+async fn create_command(
+    client: &InteractionClient<'_>,
+    guild_id: Option<Id<GuildMarker>>,
+    command: &Command,
+) -> Result<()> {
+    let name = command.name.as_str();
+    let no_localizations = HashMap::new();
+    let name_localizations = command
+        .name_localizations
+        .as_ref()
+        .unwrap_or(&no_localizations);
+    let description_localizations = command
+        .description_localizations
+        .as_ref()
+        .unwrap_or(&no_localizations);
+    let nsfw = command.nsfw.unwrap_or(false);
+
+    match (guild_id, command.kind) {
+        (Some(guild_id), CommandType::ChatInput) => {
+            let mut request = client
+                .create_guild_command(guild_id)
+                .chat_input(name, &command.description)?
+                .command_options(&command.options)?
+                .name_localizations(name_localizations)?
+                .description_localizations(description_localizations)?
+                .nsfw(nsfw);
+            if let Some(permissions) = command.default_member_permissions {
+                request = request.default_member_permissions(permissions);
+            }
+            request.await?;
+        }
+        (Some(guild_id), CommandType::Message) => {
+            let mut request = client
+                .create_guild_command(guild_id)
+                .message(name)?
+                .name_localizations(name_localizations)?
+                .nsfw(nsfw);
+            if let Some(permissions) = command.default_member_permissions {
+                request = request.default_member_permissions(permissions);
+            }
+            request.await?;
+        }
+        (None, CommandType::ChatInput) => {
+            let mut request = client
+                .create_global_command()
+                .chat_input(name, &command.description)?
+                .command_options(&command.options)?
+                .name_localizations(name_localizations)?
+                .description_localizations(description_localizations)?
+                .dm_permission(command.dm_permission.unwrap_or(true))
+                .nsfw(nsfw);
+            if let Some(permissions) = command.default_member_permissions {
+                request = request.default_member_permissions(permissions);
+            }
+            request.await?;
+        }
+        (None, CommandType::Message) => {
+            let mut request = client
+                .create_global_command()
+                .message(name)?
+                .name_localizations(name_localizations)?
+                .dm_permission(command.dm_permission.unwrap_or(true))
+                .nsfw(nsfw);
+            if let Some(permissions) = command.default_member_permissions {
+                request = request.default_member_permissions(permissions);
+            }
+            request.await?;
+        }
+        (_, kind) => bail!("Can't register {name}, {kind:?} commands aren't supported"),
+    }
+    Ok(())
+}
@@ -1,22 +1,31 @@
 #![feature(get_mut_unchecked, try_blocks, exit_status_error)]
+pub mod config;
 mod interaction;
+#[cfg(test)]
+mod tests;
 pub mod util;
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use bonsaidb::local::{
     config::{Builder, StorageConfiguration},
     AsyncDatabase,
 };
+use config::Config;
 use futures::StreamExt;
-use interaction::handle_interaction;
+use interaction::{handle_interaction, sync::sync_commands};
 use sentry::integrations::anyhow::capture_anyhow;
 use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
-use std::{env, sync::Arc};
+use std::{env, sync::Arc, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::mpsc,
+    time::{sleep, timeout},
+};
 use tokio_cron_scheduler::{Job, JobScheduler};
+use tokio_util::sync::CancellationToken;
 use twilight_cache_inmemory::{InMemoryCache, ResourceType};
 use twilight_gateway::{
-    stream::{self, ShardEventStream},
-    Config, Event, Intents, ShardId,
+    stream::ShardEventStream, CloseFrame, Config as ShardConfig, Event, Intents, Message, ShardId,
 };
 use twilight_http::Client as HttpClient;
 use twilight_model::{
@@ -28,53 +37,76 @@ use twilight_model::{
     },
 };
 use twilight_standby::Standby;
-use util::animethemes;
-
-async fn update_commands(info: ClusterData) -> Result<(usize, usize)> {
-    let client = info.http.interaction(info.application_id);
-    let globals = client.global_commands().await?.model().await?;
-    let mut deleted = 0;
-    for global in globals.iter().filter(|x| {
-        interaction::CREATE_COMMANDS
-            .iter()
-            .any(|y| y.name != x.name)
-    }) {
-        deleted += 1;
-        client.delete_global_command(global.id.unwrap()).await?;
-    }
-
-    let list = client
-        .set_global_commands(&interaction::CREATE_COMMANDS)
-        .await?
-        .model()
-        .await?;
-    Ok((list.len(), deleted))
-}
+use unic_langid::LanguageIdentifier;
+use util::{
+    animethemes,
+    cache::SauceCache,
+    censor::Censor,
+    i18n::{self, check::check_langs, get_interaction_lang},
+    metrics, mock,
+    saucenao::RateLimits,
+    settings::{GuildSettings, UserSettings},
+    shards::{create_shards, ShardStats},
+    web::WebClient,
+};
+
+/// How long in-flight interactions get to finish when shutting down.
+const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
-    let token = env::var("DISCORD_TOKEN")?;
+    if env::args().nth(1).as_deref() == Some("check-langs") {
+        let mut problems = check_langs();
+        if let Err(err) = interaction::COMMANDS.create_commands(None) {
+            problems.push(err.to_string());
+        }
+        for problem in problems.iter() {
+            println!("{problem}");
+        }
+        ensure!(
+            problems.is_empty(),
+            "Found {} problems in the lang files",
+            problems.len()
+        );
+        println!("All lang files are consistent");
+        return Ok(());
+    }
+
+    let mut config = Config::load()?;
+    i18n::set_pseudo_locale(config.i18n.pseudo_locale);
+    // Fail early if any of the command localizations is missing
+    let commands = interaction::COMMANDS.create_commands(Some(&config))?;
+    let token = config.discord.token.clone();
     let bonsai = Arc::new(
-        AsyncDatabase::open::<()>(StorageConfiguration::new(env::var("BONSAI_FILE")?)).await?,
+        AsyncDatabase::open::<()>(StorageConfiguration::new(&config.database.bonsai_file)).await?,
     );
     let pool = MySqlPoolOptions::new()
         .max_connections(20)
-        .connect(&env::var("DATABASE_URL")?)
+        .connect(&config.database.url)
         .await?;
 
     let options = sentry::ClientOptions {
-        dsn: env::var("SENTRY_DSN").ok().map(|dsn| dsn.parse().unwrap()),
+        dsn: config.sentry.as_ref().map(|x| x.dsn.parse().unwrap()),
         release: sentry::release_name!(),
         ..Default::default()
     };
     let _guard = sentry::init(options);
 
+    // The services have to point to the port it got, which is a random one when asking for 0
+    if let Some(mock) = &mut config.mock {
+        mock.addr = mock::start(mock)?;
+    }
+    let config = Arc::new(config);
+    let web = WebClient::new(&config)?;
+
     let scheduler = JobScheduler::new().await?;
     {
         let bonsai = bonsai.clone();
+        let web = web.clone();
+        let url = config.database.url.clone();
         println!("Updating AnimeThemes local cache");
-        if let Err(error) = animethemes::update_database(bonsai.clone()).await {
+        if let Err(error) = animethemes::update_database(bonsai.clone(), &web, &url).await {
             eprintln!("AnimeThemes cache failed {}", error);
         } else {
             println!("Updated AnimeThemes local cache");
@@ -82,8 +114,10 @@ async fn main() -> Result<()> {
         scheduler
             .add(Job::new_async("0 0 0 * * *", move |_uuid, _l| {
                 let bonsai = bonsai.clone();
+                let web = web.clone();
+                let url = url.clone();
                 Box::pin(async move {
-                    if let Err(error) = animethemes::update_database(bonsai).await {
+                    if let Err(error) = animethemes::update_database(bonsai, &web, &url).await {
                         eprintln!("AnimeThemes cache failed {}", error);
                     } else {
                         println!("Updated AnimeThemes cache")
@@ -105,7 +139,7 @@ async fn main() -> Result<()> {
     // Specify intents requesting events about things like new and updated
     // messages in a guild and direct messages.
     let intents = Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES;
-    let config = Config::new(token.clone(), intents);
+    let shard_config = ShardConfig::new(token.clone(), intents);
 
     // The http client is seperate from the gateway, so startup a new
     // one, also use Arc such that it can be cloned to other threads.
@@ -135,36 +169,87 @@ async fn main() -> Result<()> {
         application_id,
         cache,
         pool,
-        bonsai,
+        bonsai: bonsai.clone(),
         scheduler,
+        shards: Arc::new(ShardStats::default()),
+        saucenao_limits: Arc::new(RateLimits::default()),
+        sauce_cache: SauceCache::new(bonsai, &config.cache),
+        shutdown: CancellationToken::new(),
+        config,
+        web,
     };
 
+    if let Some(config) = &info.config.metrics {
+        let limits = info.saucenao_limits.clone();
+        metrics::start(config, move || limits.metrics())?;
+    }
+
     {
-        let (updated, deleted) = update_commands(info.clone()).await?;
-        println!("Updated {updated} commands and deleted {deleted} commands.");
+        let shards = info.shards.clone();
+        info.scheduler
+            .add(Job::new("0 */10 * * * *", move |_uuid, _l| {
+                println!("{}", shards.summary());
+            })?)
+            .await?;
+    }
+
+    {
+        // Guild commands update instantly, unlike global ones
+        let dev_guild = info.config.discord.dev_guild;
+        if let Some(guild_id) = dev_guild {
+            println!("Registering commands to development guild {guild_id}");
+        }
+        let diff = sync_commands(&info, dev_guild, &commands).await?;
+        if diff.is_empty() {
+            println!("Commands are up to date");
+        } else {
+            println!("Synced commands: {diff}");
+        }
     }
 
-    // Start a single shard.
-    let mut shards =
-        stream::create_range(0..1, 1, config, |_, builder| builder.build()).collect::<Vec<_>>();
+    let mut shards = create_shards(&http, shard_config, info.config.gateway.shards).await?;
+
+    // Every spawned handler holds a sender, so the receiver only finishes once all of them are done
+    let (in_flight, mut drained) = mpsc::channel::<()>(1);
+    let stop = shutdown_signal();
+    tokio::pin!(stop);
 
     // Create a stream to collect all of the shards and poll them for their next
     // Discord gateway events.
     let mut stream = ShardEventStream::new(shards.iter_mut());
 
     // Startup an event loop to process each event in the event stream as they
-    // come in.
-    while let Some((shard, event)) = stream.next().await {
+    // come in, until we are told to stop.
+    loop {
+        let (shard, event) = tokio::select! {
+            result = &mut stop => {
+                result?;
+                break;
+            }
+            next = stream.next() => match next {
+                Some(next) => next,
+                None => break,
+            },
+        };
         match event {
             Ok(event) => {
+                info.shards.update(&shard, Some(&event));
+
                 // Update the cache.
                 info.cache.update(&event);
 
                 // Spawn a new task to handle the event
-                tokio::spawn(handle_event(shard.id(), event, info.clone()));
+                let in_flight = in_flight.clone();
+                let handler = handle_event(shard.id(), event, info.clone());
+                tokio::spawn(async move {
+                    let result = handler.await;
+                    drop(in_flight);
+                    result
+                });
             }
             Err(source) => {
-                println!("error receiving event {}", source);
+                info.shards.update(&shard, None);
+                println!("error receiving event on shard {} {}", shard.id(), source);
 
                 // An error may be fatal when something like invalid privileged
                 // intents are specified or the Discord token is invalid.
@@ -176,6 +261,60 @@ async fn main() -> Result<()> {
             }
         };
     }
+
+    println!("Shutting down, waiting for the running interactions to finish");
+    // Stops every EmbedList so their buttons get removed instead of staying forever
+    info.shutdown.cancel();
+    drop(in_flight);
+    // The shards are still polled so they keep heartbeating, but new events are ignored
+    let deadline = sleep(SHUTDOWN_DEADLINE);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = drained.recv() => break,
+            _ = &mut deadline => {
+                eprintln!("Some interactions didn't finish in time");
+                break;
+            }
+            Some((shard, event)) = stream.next() => {
+                info.shards.update(&shard, event.as_ref().ok());
+            }
+        }
+    }
+    drop(stream);
+
+    if let Err(err) = info.scheduler.clone().shutdown().await {
+        eprintln!("Scheduler shutdown error: {:?}", err);
+    }
+
+    for shard in shards.iter_mut() {
+        if let Err(err) = shard.close(CloseFrame::NORMAL).await {
+            eprintln!("Couldn't close shard {}: {}", shard.id(), err);
+            continue;
+        }
+        // Wait for Discord to acknowledge the close
+        let closed = timeout(Duration::from_secs(5), async {
+            while let Ok(message) = shard.next_message().await {
+                if let Message::Close(_) = message {
+                    break;
+                }
+            }
+        });
+        if closed.await.is_err() {
+            eprintln!("Shard {} didn't close in time", shard.id());
+        }
+    }
+    println!("Goodbye!");
+    Ok(())
+}
+
+/// Resolves on the first SIGINT or SIGTERM.
+async fn shutdown_signal() -> Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result?,
+        _ = terminate.recv() => {}
+    }
     Ok(())
 }
 
@@ -183,9 +322,15 @@ async fn handle_event(shard: ShardId, event: Event, info: ClusterData) -> Result
     let _results = info.standby.process(&event);
     match event {
         Event::MessageCreate(msg) if msg.content.ends_with("ping") => {
+            let latency = info
+                .shards
+                .get(shard.number())
+                .and_then(|x| x.latency)
+                .map(|x| format!(" Shard {} at {}ms", shard.number(), x.as_millis()))
+                .unwrap_or_default();
             info.http
                 .create_message(msg.channel_id)
-                .content("Pong!")?
+                .content(&format!("Pong!{latency}"))?
                 .await?;
         }
         Event::InteractionCreate(interaction) => {
@@ -214,6 +359,15 @@ pub struct ClusterData {
     pub pool: Pool<MySql>,
     pub bonsai: Arc<AsyncDatabase>,
     pub scheduler: JobScheduler,
+    pub shards: Arc<ShardStats>,
+    /// Searches left in SauceNAO, shared so every shard respects the limits
+    pub saucenao_limits: Arc<RateLimits>,
+    /// Reverse image search results, kept in bonsai
+    pub sauce_cache: SauceCache,
+    /// Cancelled once the bot starts shutting down
+    pub shutdown: CancellationToken,
+    pub config: Arc<Config>,
+    pub web: WebClient,
 }
 
 impl ClusterData {
@@ -230,4 +384,28 @@ impl ClusterData {
             Ok(channel.0.nsfw.unwrap_or(false))
         }
     }
+
+    /// Language to reply in, the user's setting goes first, then the guild's one
+    /// and lastly the locale of the user's client.
+    pub async fn get_lang(&self, interaction: &Interaction) -> Result<&'static LanguageIdentifier> {
+        if let Some(user_id) = interaction.author_id() {
+            if let Some(lang) = UserSettings::get(&self.bonsai, user_id).await?.lang() {
+                return Ok(lang);
+            }
+        }
+        if let Some(guild_id) = interaction.guild_id {
+            if let Some(lang) = GuildSettings::get(&self.bonsai, guild_id).await?.lang() {
+                return Ok(lang);
+            }
+        }
+        Ok(get_interaction_lang(interaction))
+    }
+
+    /// How the guild of the interaction handles NSFW images, DMs use the defaults.
+    pub async fn get_censor(&self, interaction: &Interaction) -> Result<Censor> {
+        match interaction.guild_id {
+            Some(guild_id) => Ok(GuildSettings::get(&self.bonsai, guild_id).await?.censor()),
+            None => Ok(Censor::default()),
+        }
+    }
 }
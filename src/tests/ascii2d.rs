@@ -0,0 +1,116 @@
+use std::fs;
+
+use unic_langid::langid;
+
+use super::{discord::DiscordRequest, sauce::subcommand, Harness};
+use crate::util::{
+    ascii2d::{build_embed, parse, Ascii2dMatch},
+    censor::{Censor, NsfwPolicy},
+};
+
+#[test]
+fn parses_the_linked_items() {
+    let html = fs::read_to_string("fixtures/mock/pages/ascii2d.html").unwrap();
+    let matches = parse(&html, "https://ascii2d.net").unwrap();
+    // The uploaded image and the item without links are left out
+    assert_eq!(
+        matches,
+        [
+            Ascii2dMatch {
+                title: "Summer walk".to_string(),
+                url: "https://www.pixiv.net/artworks/98765432".to_string(),
+                author: Some((
+                    "example".to_string(),
+                    "https://www.pixiv.net/users/1234".to_string()
+                )),
+                site: "pixiv".to_string(),
+                thumbnail: "https://ascii2d.net/thumbnail/7/7/7/7/7777.jpg".to_string(),
+                details: Some("1200x900 JPEG 300.5KB".to_string()),
+            },
+            Ascii2dMatch {
+                title: "2023.07.01".to_string(),
+                url: "https://twitter.com/example/status/1".to_string(),
+                author: Some((
+                    "example".to_string(),
+                    "https://twitter.com/intent/user?user_id=42".to_string()
+                )),
+                site: "twitter".to_string(),
+                thumbnail: "https://ascii2d.net/thumbnail/9/9/9/9/9999.jpg".to_string(),
+                details: Some("1200x900 PNG 1.2MB".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn error_pages_have_no_results() {
+    assert_eq!(parse("<html><body>Not an image</body></html>", ""), None);
+}
+
+#[test]
+fn items_without_a_link_are_left_out() {
+    let html = r#"<div class="item-box">
+        <div class="image-box"><img src="/thumbnail/1/1/1/1/1111.jpg"></div>
+        <div class="detail-box"><h6><a>Untitled</a> <small>pixiv</small></h6></div>
+    </div>"#;
+    assert_eq!(parse(html, "https://ascii2d.net"), Some(Vec::new()));
+}
+
+#[tokio::test]
+async fn follows_the_nsfw_policy() {
+    let harness = Harness::new().await;
+    let html = fs::read_to_string("fixtures/mock/pages/ascii2d.html").unwrap();
+    let matches = parse(&html, &format!("{}/ascii2d", harness.mock_url())).unwrap();
+    let lang = langid!("en-US");
+
+    let cases = [
+        (NsfwPolicy::Hide, None, false),
+        (NsfwPolicy::Spoiler, Some("SPOILER_ascii2d.png"), false),
+        (NsfwPolicy::Allow, Some("ascii2d.png"), true),
+    ];
+    for (policy, file, embedded) in cases {
+        let censor = Censor {
+            policy,
+            ..Censor::default()
+        };
+        let (embed, attachment) =
+            build_embed(&harness.info.web, &matches[0], false, censor, false, &lang)
+                .await
+                .unwrap();
+        let embed = embed.build();
+        assert_eq!(
+            attachment.map(|x| x.filename).as_deref(),
+            file,
+            "{policy:?}"
+        );
+        assert_eq!(embed.image.is_some(), embedded, "{policy:?}");
+        // Only said when something was done about it
+        assert_eq!(
+            embed.description.is_some(),
+            policy != NsfwPolicy::Allow,
+            "{policy:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn censors_the_thumbnails_outside_nsfw_channels() {
+    let harness = Harness::new().await;
+    let command = harness.send(harness.command("sauce", subcommand("ascii2d"), &["thumbnail.png"]));
+
+    let first = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = first.embed();
+    assert_eq!(embed["title"], "Summer walk");
+    assert_eq!(embed["url"], "https://www.pixiv.net/artworks/98765432");
+    assert_eq!(embed["author"]["name"], "example");
+    assert_eq!(embed["footer"]["text"], "pixiv · 1200x900 JPEG 300.5KB");
+    assert_eq!(embed["image"]["url"], "attachment://ascii2d.png");
+    assert!(embed["description"]
+        .as_str()
+        .unwrap()
+        .starts_with("ascii2d doesn't say if images are NSFW"));
+    assert_eq!(first.files, ["ascii2d.png"]);
+
+    harness.info.shutdown.cancel();
+    command.await.unwrap().unwrap();
+}
@@ -0,0 +1,133 @@
+use std::{fs, io::Cursor};
+
+use image::{ImageOutputFormat, Rgb, RgbImage};
+use serde_json::json;
+use twilight_model::id::Id;
+
+use super::{discord::DiscordRequest, sauce::subcommand, Harness, USER_ID};
+use crate::util::cache::ImageHashes;
+
+/// Runs `/sauce {engine}` with the image and returns the footer of the embed sent.
+async fn search(harness: &Harness, engine: &str, image: &str) -> String {
+    let command = harness.command("sauce", subcommand(engine), &[image]);
+    harness.send(command).await.unwrap().unwrap();
+    // Taken out of the way of the callbacks the tests look for
+    harness.discord.next(DiscordRequest::is_callback).await;
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    followup.embed()["footer"]["text"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[test]
+fn resized_copies_look_the_same() {
+    let image = ImageHashes::new(&fs::read("fixtures/mock/files/gradient.png").unwrap());
+    let small = ImageHashes::new(&fs::read("fixtures/mock/files/gradient-small.png").unwrap());
+    assert_ne!(image.content, small.content);
+    assert!(image.perceptual.is_some());
+    assert_eq!(image.perceptual, small.perceptual);
+}
+
+#[test]
+fn flat_images_have_no_perceptual_hash() {
+    let solid = |color| {
+        let mut bytes = Vec::new();
+        RgbImage::from_pixel(64, 64, Rgb(color))
+            .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+            .unwrap();
+        ImageHashes::new(&bytes)
+    };
+    let (red, blue) = (solid([255, 0, 0]), solid([0, 0, 255]));
+    assert_ne!(red.content, blue.content);
+    // Otherwise both would share the cached results of the other
+    assert_eq!(red.perceptual, None);
+    assert_eq!(blue.perceptual, None);
+}
+
+#[test]
+fn not_an_image_has_no_perceptual_hash() {
+    let hashes = ImageHashes::new(b"not an image");
+    assert_eq!(hashes.perceptual, None);
+}
+
+#[tokio::test]
+async fn saucenao_reuses_the_search() {
+    let harness = Harness::new().await;
+    let footer = search(&harness, "saucenao", "thumbnail.png").await;
+    assert_eq!(footer, "Index #9: Danbooru - 1234567.jpg");
+    let footer = search(&harness, "saucenao", "thumbnail.png").await;
+    assert_eq!(footer, "Index #9: Danbooru - 1234567.jpg · cached");
+
+    // Cached searches don't spend any of the limits
+    assert!(harness
+        .info
+        .saucenao_limits
+        .metrics()
+        .contains("saucenao_short_remaining 3\n"));
+}
+
+#[tokio::test]
+async fn saucenao_finds_resized_copies() {
+    let harness = Harness::new().await;
+    search(&harness, "saucenao", "gradient.png").await;
+    let footer = search(&harness, "saucenao", "gradient-small.png").await;
+    assert!(footer.ends_with(" · cached"));
+}
+
+#[tokio::test]
+async fn tracemoe_reuses_the_search() {
+    let harness = Harness::new().await;
+    assert_eq!(
+        search(&harness, "tracemoe", "thumbnail.png").await,
+        "958 searches left"
+    );
+    assert_eq!(
+        search(&harness, "tracemoe", "thumbnail.png").await,
+        "cached"
+    );
+}
+
+#[tokio::test]
+async fn zero_ttl_turns_it_off() {
+    let harness = Harness::with_config(|config| config.cache.ttl = 0).await;
+    search(&harness, "saucenao", "thumbnail.png").await;
+    let footer = search(&harness, "saucenao", "thumbnail.png").await;
+    assert!(!footer.contains("cached"));
+}
+
+#[tokio::test]
+async fn admins_purge_it() {
+    let harness =
+        Harness::with_config(|config| config.discord.admins = vec![Id::new(USER_ID)]).await;
+    search(&harness, "saucenao", "thumbnail.png").await;
+
+    let command = harness.command("admin", json!([{ "name": "purge-cache", "type": 1 }]), &[]);
+    harness.send(command).await.unwrap().unwrap();
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.flags(), 1 << 6);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("Forgot every cached search"));
+
+    let footer = search(&harness, "saucenao", "thumbnail.png").await;
+    assert!(!footer.contains("cached"));
+}
+
+#[tokio::test]
+async fn only_admins_purge_it() {
+    let harness = Harness::with_config(|config| config.discord.admins = vec![Id::new(1)]).await;
+    search(&harness, "saucenao", "thumbnail.png").await;
+
+    let command = harness.command("admin", json!([{ "name": "purge-cache", "type": 1 }]), &[]);
+    harness.send(command).await.unwrap().unwrap();
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("Only the admins of the bot can do this"));
+
+    let footer = search(&harness, "saucenao", "thumbnail.png").await;
+    assert!(footer.ends_with(" · cached"));
+}
@@ -0,0 +1,142 @@
+use std::{io::Cursor, time::Duration};
+
+use image::{
+    codecs::gif::{GifDecoder, GifEncoder},
+    AnimationDecoder, Delay, DynamicImage, Frame, ImageFormat, ImageOutputFormat, Rgba, RgbaImage,
+};
+use serde_json::json;
+
+use super::{discord::DiscordRequest, Harness};
+use crate::util::censor::{censor_image, Censor, CensorMethod, DEFAULT_STRENGTH};
+
+const METHODS: [CensorMethod; 3] = [
+    CensorMethod::Pixelate,
+    CensorMethod::Blur,
+    CensorMethod::Overlay,
+];
+
+fn gradient(width: u32, height: u32, shift: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([
+            (x * 7 + shift) as u8,
+            (y * 5) as u8,
+            ((x + y) * 3) as u8,
+            255,
+        ])
+    })
+}
+
+fn png(image: RgbaImage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgba8(image)
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn tiny_images_get_censored_too() {
+    // Pixelating used to divide by zero with anything narrower than 18 pixels
+    for (width, height) in [(1, 1), (3, 40), (40, 3)] {
+        for method in METHODS {
+            let censor = Censor::new(method, 100).unwrap();
+            let (censored, _) = censor_image(png(gradient(width, height, 0)).into(), censor)
+                .await
+                .unwrap_or_else(|err| panic!("{method:?} on {width}×{height}: {err:?}"));
+            let image = image::load_from_memory(&censored).unwrap();
+            assert_eq!((image.width(), image.height()), (width, height));
+        }
+    }
+
+    // Thumbnails this small used to come back untouched with the default strength
+    let original = gradient(30, 30, 0);
+    for method in METHODS {
+        let censor = Censor::new(method, DEFAULT_STRENGTH).unwrap();
+        let (censored, _) = censor_image(png(original.clone()).into(), censor)
+            .await
+            .unwrap();
+        let image = image::load_from_memory(&censored).unwrap().into_rgba8();
+        assert_ne!(image, original, "{method:?}");
+    }
+}
+
+#[tokio::test]
+async fn keeps_the_size_but_not_the_details() {
+    let original = gradient(120, 80, 0);
+    for method in METHODS {
+        for strength in [1, 50, 100] {
+            let censor = Censor::new(method, strength).unwrap();
+            let (censored, format) = censor_image(png(original.clone()).into(), censor)
+                .await
+                .unwrap();
+            assert_eq!(format, ImageFormat::Png);
+            let image = image::load_from_memory(&censored).unwrap().into_rgba8();
+            assert_eq!(image.dimensions(), original.dimensions());
+            assert_ne!(image, original, "{method:?} at {strength}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn animated_gifs_stay_animated() {
+    let delay = Delay::from_saturating_duration(Duration::from_millis(100));
+    let frames = (0..3).map(|x| Frame::from_parts(gradient(40, 30, x * 80), 0, 0, delay));
+    let mut gif = Vec::new();
+    GifEncoder::new(&mut gif).encode_frames(frames).unwrap();
+
+    let (censored, format) = censor_image(gif.into(), Censor::default()).await.unwrap();
+    assert_eq!(format, ImageFormat::Gif);
+    let frames = GifDecoder::new(Cursor::new(&censored[..]))
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(frames.len(), 3);
+    for frame in frames {
+        assert_eq!(frame.buffer().dimensions(), (40, 30));
+        assert_eq!(Duration::from(frame.delay()), Duration::from_millis(100));
+    }
+}
+
+#[tokio::test]
+async fn long_animations_only_keep_their_first_frame() {
+    let delay = Delay::from_saturating_duration(Duration::from_millis(20));
+    let frames = (0..150).map(|x| Frame::from_parts(gradient(8, 8, x), 0, 0, delay));
+    let mut gif = Vec::new();
+    GifEncoder::new(&mut gif).encode_frames(frames).unwrap();
+
+    let (censored, format) = censor_image(gif.into(), Censor::default()).await.unwrap();
+    assert_eq!(format, ImageFormat::Png);
+    let image = image::load_from_memory(&censored).unwrap();
+    assert_eq!((image.width(), image.height()), (8, 8));
+}
+
+#[test]
+fn rejects_strengths_out_of_range() {
+    assert!(Censor::new(CensorMethod::Blur, 0).is_err());
+    assert!(Censor::new(CensorMethod::Blur, 101).is_err());
+}
+
+#[tokio::test]
+async fn settings_need_a_server() {
+    let harness = Harness::new().await;
+    let cases = [
+        ("censor", "Censoring can only be changed inside a server"),
+        (
+            "nsfw",
+            "What happens to NSFW images can only be changed inside a server",
+        ),
+    ];
+    for (subcommand, text) in cases {
+        let options = json!([{ "name": subcommand, "type": 1, "options": [] }]);
+        harness
+            .send(harness.command("settings", options, &[]))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let reply = harness.discord.next(DiscordRequest::is_callback).await;
+        assert_eq!(reply.flags(), 1 << 6);
+        assert!(reply.content().unwrap().starts_with(text), "{subcommand}");
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hyper::{
+    header::CONTENT_TYPE,
+    server::conn::AddrIncoming,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+use tokio::{sync::Notify, time::timeout};
+
+use super::{APPLICATION_ID, CHANNEL_ID, MESSAGE_ID};
+
+/// How long to wait for the bot to send something before failing the test.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A request the bot sent to Discord, with the multipart forms already taken apart.
+#[derive(Clone, Debug)]
+pub struct DiscordRequest {
+    pub method: Method,
+    /// Without the `/api/v10` prefix
+    pub path: String,
+    /// The JSON body, or the `payload_json` part of a multipart one
+    pub body: Value,
+    /// Names of the uploaded files
+    pub files: Vec<String>,
+}
+
+impl DiscordRequest {
+    /// Answers to an interaction, like deferring it.
+    pub fn is_callback(&self) -> bool {
+        self.method == Method::POST && self.path.ends_with("/callback")
+    }
+
+    pub fn is_followup(&self) -> bool {
+        self.method == Method::POST && self.path.starts_with("/webhooks/")
+    }
+
+    /// Edits to the original response, like the ones from the EmbedList buttons.
+    pub fn is_edit(&self) -> bool {
+        self.method == Method::PATCH && self.path.ends_with("/messages/@original")
+    }
+
+    /// Callbacks keep the message in `data`, the rest send it as the body.
+    fn message(&self) -> &Value {
+        self.body.get("data").unwrap_or(&self.body)
+    }
+
+    pub fn content(&self) -> Option<&str> {
+        self.message()["content"].as_str()
+    }
+
+    pub fn flags(&self) -> u64 {
+        self.message()["flags"].as_u64().unwrap_or(0)
+    }
+
+    pub fn embeds(&self) -> &[Value] {
+        self.message()["embeds"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The only embed sent, fails if there isn't exactly one.
+    pub fn embed(&self) -> &Value {
+        match self.embeds() {
+            [embed] => embed,
+            embeds => panic!("Expected one embed, got {embeds:#?}"),
+        }
+    }
+
+    /// `(custom_id, disabled)` of every button, `None` if components weren't sent at all.
+    pub fn buttons(&self) -> Option<Vec<(String, bool)>> {
+        let rows = self.message()["components"].as_array()?;
+        Some(
+            rows.iter()
+                .filter_map(|row| row["components"].as_array())
+                .flatten()
+                .map(|button| {
+                    (
+                        button["custom_id"].as_str().unwrap_or_default().to_string(),
+                        button["disabled"].as_bool().unwrap_or(false),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// `(name, value)` of every field of the embed.
+pub fn fields(embed: &Value) -> Vec<(&str, &str)> {
+    embed["fields"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|x| {
+            (
+                x["name"].as_str().unwrap_or_default(),
+                x["value"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+/// `(name, value)` of every autocomplete choice, only for string values.
+pub fn choices(request: &DiscordRequest) -> Vec<(&str, &str)> {
+    request.body["data"]["choices"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|x| {
+            (
+                x["name"].as_str().unwrap_or_default(),
+                x["value"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct State {
+    pending: Mutex<VecDeque<DiscordRequest>>,
+    received: Notify,
+}
+
+/// Just enough of the Discord REST API for the interaction endpoints, everything the bot
+/// sends is recorded so the tests can take it out in order.
+pub struct FakeDiscord {
+    pub addr: SocketAddr,
+    state: Arc<State>,
+}
+
+impl FakeDiscord {
+    pub fn start() -> Self {
+        let incoming = AddrIncoming::bind(&([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = incoming.local_addr();
+        let state = Arc::new(State::default());
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(&state, req).await) }
+                }))
+            }
+        });
+        tokio::spawn(Server::builder(incoming).serve(make_service));
+        FakeDiscord { addr, state }
+    }
+
+    /// Takes out the first request matching `predicate`, waiting for it if it didn't arrive yet.
+    pub async fn next(&self, predicate: impl Fn(&DiscordRequest) -> bool) -> DiscordRequest {
+        match self.next_within(REQUEST_TIMEOUT, predicate).await {
+            Some(request) => request,
+            None => panic!(
+                "Discord didn't get the expected request, pending ones: {:#?}",
+                self.state.pending.lock().unwrap()
+            ),
+        }
+    }
+
+    pub async fn next_within(
+        &self,
+        duration: Duration,
+        predicate: impl Fn(&DiscordRequest) -> bool,
+    ) -> Option<DiscordRequest> {
+        timeout(duration, async {
+            loop {
+                // Registered before looking so a request arriving in between isn't missed
+                let received = self.state.received.notified();
+                {
+                    let mut pending = self.state.pending.lock().unwrap();
+                    if let Some(index) = pending.iter().position(&predicate) {
+                        return pending.remove(index).unwrap();
+                    }
+                }
+                received.await;
+            }
+        })
+        .await
+        .ok()
+    }
+}
+
+async fn handle(state: &State, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().trim_start_matches("/api/v10").to_string();
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let bytes = hyper::body::to_bytes(req.into_body())
+        .await
+        .unwrap_or_default();
+    let (body, files) = parse_body(&content_type, &bytes);
+
+    let response = route(&path);
+    state.pending.lock().unwrap().push_back(DiscordRequest {
+        method,
+        path,
+        body,
+        files,
+    });
+    state.received.notify_waiters();
+    response
+}
+
+fn route(path: &str) -> Response<Body> {
+    if path.ends_with("/callback") {
+        Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+    } else if path.starts_with("/webhooks/") {
+        Response::builder()
+            .header(CONTENT_TYPE, "application/json")
+            .body(message_json().to_string().into())
+    } else {
+        eprintln!("The fake Discord doesn't know {path}");
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(CONTENT_TYPE, "application/json")
+            .body(
+                json!({ "code": 0, "message": "404: Not Found" })
+                    .to_string()
+                    .into(),
+            )
+    }
+    .unwrap()
+}
+
+/// Splits the multipart forms used for attachments, the files are only kept by name.
+fn parse_body(content_type: &str, bytes: &[u8]) -> (Value, Vec<String>) {
+    let boundary = if let Some(boundary) = content_type.split("boundary=").nth(1) {
+        boundary
+    } else {
+        return (
+            serde_json::from_slice(bytes).unwrap_or(Value::Null),
+            Vec::new(),
+        );
+    };
+
+    let text = String::from_utf8_lossy(bytes);
+    let mut body = Value::Null;
+    let mut files = Vec::new();
+    for part in text.split(&format!("--{boundary}")) {
+        if let Some((headers, content)) = part.split_once("\r\n\r\n") {
+            if headers.contains("name=\"payload_json\"") {
+                body = serde_json::from_str(content.trim_end()).unwrap_or(Value::Null);
+            } else if let Some(filename) = headers
+                .split("filename=\"")
+                .nth(1)
+                .and_then(|x| x.split('"').next())
+            {
+                files.push(filename.to_string());
+            }
+        }
+    }
+    (body, files)
+}
+
+/// The message every webhook endpoint answers with, it's the one the buttons are attached to.
+pub fn message_json() -> Value {
+    json!({
+        "id": MESSAGE_ID.to_string(),
+        "channel_id": CHANNEL_ID.to_string(),
+        "author": {
+            "id": APPLICATION_ID.to_string(),
+            "username": "sweetiebot",
+            "discriminator": "0001",
+            "avatar": null,
+            "bot": true
+        },
+        "content": "",
+        "timestamp": "2023-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "components": [],
+        "pinned": false,
+        "type": 0,
+        "flags": 0
+    })
+}
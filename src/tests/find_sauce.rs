@@ -0,0 +1,174 @@
+use serde_json::{json, Value};
+use twilight_model::channel::Message;
+
+use super::{
+    discord::{message_json, DiscordRequest},
+    sauce::subcommand,
+    Harness,
+};
+use crate::util::{find_images, ImageOrigin};
+
+fn attachment(id: u64, url: &str, filename: &str, content_type: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "filename": filename,
+        "size": 0,
+        "url": url,
+        "proxy_url": url,
+        "content_type": content_type
+    })
+}
+
+/// A link that Discord embedded as its thumbnail.
+fn link_embed(url: &str) -> Value {
+    json!({ "type": "image", "url": url, "thumbnail": { "url": url, "proxy_url": url } })
+}
+
+#[test]
+fn finds_every_image_once() {
+    let mut message = message_json();
+    message["content"] = json!(
+        "look https://example.com/a.png and https://example.com/b.JPG?size=large or https://example.com/page"
+    );
+    message["attachments"] = json!([
+        attachment(
+            1,
+            "https://cdn.example.com/notes.txt",
+            "notes.txt",
+            "text/plain"
+        ),
+        attachment(
+            2,
+            "https://cdn.example.com/clip.mp4",
+            "clip.mp4",
+            "video/mp4"
+        ),
+    ]);
+    message["embeds"] = json!([link_embed("https://example.com/a.png")]);
+    let message: Message = serde_json::from_value(message).unwrap();
+
+    let images = find_images(&message)
+        .into_iter()
+        .map(|(origin, image)| (origin, image.url, image.content_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        images,
+        [
+            (
+                ImageOrigin::Attachment,
+                "https://cdn.example.com/clip.mp4".to_string(),
+                Some("video/mp4".to_string())
+            ),
+            (
+                ImageOrigin::Embed,
+                "https://example.com/a.png".to_string(),
+                Some("image/png".to_string())
+            ),
+            (
+                ImageOrigin::Link,
+                "https://example.com/b.JPG?size=large".to_string(),
+                Some("image/jpeg".to_string())
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn searches_the_only_image() {
+    let harness = Harness::new().await;
+    let url = format!("{}/files/thumbnail.png", harness.mock_url());
+    let command = harness.message_command(
+        "Find sauce",
+        json!({ "attachments": [attachment(1, &url, "thumbnail.png", "image/png")] }),
+    );
+    let handle = harness.send(command);
+
+    let picker = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(picker.body["type"], 4);
+    assert_eq!(picker.flags(), 1 << 6);
+    assert_eq!(
+        picker.content(),
+        Some("Which image do you want the sauce of?")
+    );
+    // No need to choose the image
+    assert_eq!(
+        picker.buttons(),
+        Some(vec![
+            ("saucenao".to_string(), false),
+            ("tracemoe".to_string(), false)
+        ])
+    );
+
+    let deferred = harness.click("saucenao").await;
+    assert_eq!(deferred.body["type"], 5);
+    let cleared = harness.discord.next(DiscordRequest::is_edit).await;
+    assert_eq!(cleared.buttons(), Some(Vec::new()));
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(
+        followup.embed()["footer"]["text"],
+        "Index #9: Danbooru - 1234567.jpg"
+    );
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn searches_the_chosen_image() {
+    let harness = Harness::new().await;
+    let thumbnail = format!("{}/files/thumbnail.png", harness.mock_url());
+    let gradient = format!("{}/files/gradient.png", harness.mock_url());
+    let command = harness.message_command(
+        "Find sauce",
+        json!({
+            "content": format!("the second one {gradient}"),
+            "attachments": [attachment(1, &thumbnail, "thumbnail.png", "image/png")]
+        }),
+    );
+    let handle = harness.send(command);
+
+    let picker = harness.discord.next(DiscordRequest::is_callback).await;
+    let menu = &picker.body["data"]["components"][0]["components"][0];
+    let options = menu["options"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| {
+            (
+                x["label"].as_str().unwrap(),
+                x["description"].as_str().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        options,
+        [
+            ("1. thumbnail.png", "Attachment"),
+            ("2. gradient.png", "Link")
+        ]
+    );
+
+    assert_eq!(harness.select("image", &["1"]).await.body["type"], 6);
+    assert_eq!(harness.click("tracemoe").await.body["type"], 5);
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(followup.embed()["title"], "ONE PIECE");
+    handle.await.unwrap().unwrap();
+
+    // Only the chosen one got searched, so it's the one in the cache
+    let command = harness.command("sauce", subcommand("tracemoe"), &["gradient.png"]);
+    harness.send(command).await.unwrap().unwrap();
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(followup.embed()["footer"]["text"], "cached");
+}
+
+#[tokio::test]
+async fn needs_an_image() {
+    let harness = Harness::new().await;
+    let command = harness.message_command("Find sauce", json!({ "content": "no images here" }));
+    harness.send(command).await.unwrap().unwrap();
+
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.flags(), 1 << 6);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("There aren't any images in that message"));
+}
@@ -0,0 +1,19 @@
+use crate::{
+    interaction::COMMANDS,
+    util::i18n::{check::check_langs, AVAILABLE_LANGS},
+};
+
+#[test]
+fn lang_files_are_consistent() {
+    assert_eq!(check_langs(), Vec::<String>::new());
+}
+
+#[test]
+fn every_command_is_localized() {
+    // Without a config every command is built, even the ones it would turn off
+    let commands = COMMANDS.create_commands(None).unwrap();
+    for command in commands {
+        let names = command.name_localizations.unwrap();
+        assert_eq!(names.len(), AVAILABLE_LANGS.len(), "{}", command.name);
+    }
+}
@@ -0,0 +1,63 @@
+use std::fs;
+
+use super::{discord::DiscordRequest, sauce::subcommand, Harness};
+use crate::util::iqdb::{parse, IqdbMatch, Rating};
+
+#[test]
+fn parses_the_matches() {
+    let html = fs::read_to_string("fixtures/mock/pages/iqdb.html").unwrap();
+    let matches = parse(&html, "https://iqdb.org").unwrap();
+    // The uploaded image and the possible match are left out
+    assert_eq!(
+        matches,
+        [
+            IqdbMatch {
+                url: "https://danbooru.donmai.us/posts/1234567".to_string(),
+                thumbnail: "https://iqdb.org/danbooru/f/a/8/fa8c1b2d.jpg".to_string(),
+                site: "Danbooru".to_string(),
+                size: Some("1200×900".to_string()),
+                rating: Rating::Safe,
+                similarity: 95.0,
+            },
+            IqdbMatch {
+                url: "https://yande.re/post/show/222222".to_string(),
+                thumbnail: "https://iqdb.org/moe.imouto/2/2/2/222222.jpg".to_string(),
+                site: "yande.re".to_string(),
+                size: Some("2400×1800".to_string()),
+                rating: Rating::Explicit,
+                similarity: 91.0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn error_pages_have_no_results() {
+    assert_eq!(parse("<html><body>Not an image</body></html>", ""), None);
+}
+
+#[tokio::test]
+async fn sends_the_matches() {
+    let harness = Harness::new().await;
+    let command = harness.send(harness.command("sauce", subcommand("iqdb"), &["thumbnail.png"]));
+
+    let first = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = first.embed();
+    assert_eq!(embed["title"], "https://danbooru.donmai.us/posts/1234567");
+    assert_eq!(embed["url"], "https://danbooru.donmai.us/posts/1234567");
+    assert_eq!(embed["description"], "Similarity 95%");
+    assert_eq!(embed["footer"]["text"], "Danbooru · 1200×900");
+    assert_eq!(embed["image"]["url"], "attachment://iqdb.png");
+    assert_eq!(first.files, ["iqdb.png"]);
+
+    // Explicit posts get censored outside NSFW channels
+    harness.click("next").await;
+    let second = harness.discord.next(DiscordRequest::is_edit).await;
+    assert_eq!(
+        second.embed()["description"],
+        "Similarity 91%\n**WARNING**: Image is NSFW so it's been censored!"
+    );
+
+    harness.info.shutdown.cancel();
+    command.await.unwrap().unwrap();
+}
@@ -0,0 +1,79 @@
+use serde_json::json;
+
+use super::{
+    discord::{choices, fields, DiscordRequest},
+    Harness,
+};
+
+#[tokio::test]
+async fn pages_through_the_words() {
+    let harness = Harness::new().await;
+    let command = harness.send(harness.command(
+        "japanese",
+        json!([{ "name": "word", "type": 3, "value": "猫" }]),
+        &[],
+    ));
+
+    let deferred = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(deferred.body["type"], 5);
+
+    let first = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = first.embed();
+    assert_eq!(embed["title"], "猫");
+    assert_eq!(embed["url"], "https://jisho.org/word/猫");
+    assert_eq!(embed["color"], 0x56_D9_26);
+    assert_eq!(embed["description"], "**common word - wanikani lvl3**");
+    assert_eq!(embed["thumbnail"]["url"], "attachment://furigana.png");
+    assert_eq!(
+        fields(embed),
+        [("Noun", "1. **cat** "), ("Other forms", "ネコ")]
+    );
+    assert_eq!(first.files, ["furigana.png"]);
+    assert_eq!(
+        first.buttons().unwrap(),
+        [("back".to_string(), true), ("next".to_string(), false)]
+    );
+
+    let pressed = harness.click("next").await;
+    assert_eq!(pressed.body["type"], 6);
+    let second = harness.discord.next(DiscordRequest::is_edit).await;
+    let embed = second.embed();
+    assert_eq!(embed["title"], "猫舌");
+    assert!(embed.get("description").is_none());
+    assert_eq!(
+        fields(embed),
+        [("Noun, No-adjective", "1. **being sensitive to hot food** ")]
+    );
+    assert_eq!(second.files, ["furigana.png"]);
+    assert_eq!(
+        second.buttons().unwrap(),
+        [("back".to_string(), false), ("next".to_string(), true)]
+    );
+
+    harness.click("back").await;
+    let back = harness.discord.next(DiscordRequest::is_edit).await;
+    assert_eq!(back.embed()["title"], "猫");
+
+    // Shutting down takes the buttons out
+    harness.info.shutdown.cancel();
+    let cleared = harness.discord.next(DiscordRequest::is_edit).await;
+    assert!(cleared.buttons().unwrap().is_empty());
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn autocompletes_the_word() {
+    let harness = Harness::new().await;
+    let interaction = harness.autocomplete(
+        "japanese",
+        json!([{ "name": "word", "type": 3, "value": "ねこ", "focused": true }]),
+    );
+    harness.send(interaction).await.unwrap().unwrap();
+
+    let response = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(response.body["type"], 8);
+    assert_eq!(
+        choices(&response),
+        [("ねこ", "ねこ"), ("\"ねこ\"", "\"ねこ\"")]
+    );
+}
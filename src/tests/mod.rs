@@ -0,0 +1,277 @@
+//! End-to-end tests of the commands. Interactions are fed to `handle_event` like the gateway
+//! would, the services are answered by the mock fixtures and Discord by [`FakeDiscord`].
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use bonsaidb::local::{
+    config::{Builder, StorageConfiguration},
+    AsyncDatabase,
+};
+use serde_json::{json, Value};
+use sqlx::mysql::MySqlPoolOptions;
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+use tokio_cron_scheduler::JobScheduler;
+use tokio_util::sync::CancellationToken;
+use twilight_cache_inmemory::{InMemoryCache, ResourceType};
+use twilight_gateway::{Event, ShardId};
+use twilight_http::Client as HttpClient;
+use twilight_model::{
+    application::interaction::Interaction, gateway::payload::incoming::InteractionCreate, id::Id,
+};
+use twilight_standby::Standby;
+
+use crate::{
+    config::Config,
+    handle_event,
+    util::{cache::SauceCache, mock, saucenao::RateLimits, shards::ShardStats, web::WebClient},
+    ClusterData,
+};
+
+use self::discord::{message_json, DiscordRequest, FakeDiscord};
+
+mod ascii2d;
+mod cache;
+mod censor;
+mod discord;
+mod find_sauce;
+mod i18n;
+mod iqdb;
+mod japanese;
+mod op;
+mod sauce;
+mod saucenao;
+mod sync;
+mod web;
+mod yandex;
+
+pub const APPLICATION_ID: u64 = 1000;
+pub const CHANNEL_ID: u64 = 3000;
+pub const USER_ID: u64 = 4000;
+/// Id of every message the bot "sends"
+pub const MESSAGE_ID: u64 = 6000;
+/// Id of the first attachment, the next ones count up from it
+pub const ATTACHMENT_ID: u64 = 7000;
+
+/// Button presses are retried until someone answers them, since the EmbedList only
+/// starts listening after fetching its message.
+const CLICK_ATTEMPTS: u32 = 20;
+
+const CONFIG: &str = r#"
+[discord]
+token = "test-token"
+
+[database]
+url = "mysql://localhost/sweetiebot"
+bonsai_file = "unused"
+
+[http]
+retries = 0
+
+[saucenao]
+token = "test-saucenao"
+
+[mock]
+addr = "127.0.0.1:0"
+fixtures = "fixtures/mock"
+"#;
+
+/// A bot wired to the fake Discord and the mock services.
+pub struct Harness {
+    pub info: ClusterData,
+    pub discord: FakeDiscord,
+    next_id: AtomicU64,
+    _bonsai_dir: TempDir,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    /// Lets the test change the config before anything gets started with it.
+    pub async fn with_config(edit: impl FnOnce(&mut Config)) -> Self {
+        let mut config: Config = toml::from_str(CONFIG).unwrap();
+        edit(&mut config);
+        if let Some(mock) = &mut config.mock {
+            let addr = mock::start(mock).unwrap();
+            mock.addr = addr;
+        }
+
+        let discord = FakeDiscord::start();
+        let http = HttpClient::builder()
+            .token(config.discord.token.clone())
+            .proxy(discord.addr.to_string(), true)
+            .ratelimiter(None)
+            .build();
+        let bonsai_dir = tempfile::tempdir().unwrap();
+        let bonsai =
+            AsyncDatabase::open::<()>(StorageConfiguration::new(bonsai_dir.path().join("bonsai")))
+                .await
+                .unwrap();
+        let bonsai = Arc::new(bonsai);
+        // Only tests with a database actually connect
+        let pool = MySqlPoolOptions::new()
+            .connect_lazy(&config.database.url)
+            .unwrap();
+        let web = WebClient::new(&config).unwrap();
+
+        let info = ClusterData {
+            http: Arc::new(http),
+            application_id: Id::new(APPLICATION_ID),
+            bot_id: Id::new(APPLICATION_ID),
+            standby: Arc::new(Standby::new()),
+            cache: Arc::new(
+                InMemoryCache::builder()
+                    .resource_types(ResourceType::CHANNEL)
+                    .build(),
+            ),
+            pool,
+            bonsai: bonsai.clone(),
+            scheduler: JobScheduler::new().await.unwrap(),
+            shards: Arc::new(ShardStats::default()),
+            saucenao_limits: Arc::new(RateLimits::default()),
+            sauce_cache: SauceCache::new(bonsai, &config.cache),
+            shutdown: CancellationToken::new(),
+            config: Arc::new(config),
+            web,
+        };
+        Harness {
+            info,
+            discord,
+            next_id: AtomicU64::new(10_000),
+            _bonsai_dir: bonsai_dir,
+        }
+    }
+
+    /// Handles the interaction in the background, like the event loop does.
+    pub fn send(&self, interaction: Interaction) -> JoinHandle<Result<()>> {
+        let event = Event::InteractionCreate(Box::new(InteractionCreate(interaction)));
+        tokio::spawn(handle_event(ShardId::ONE, event, self.info.clone()))
+    }
+
+    /// A slash command used in DMs, `options` are sent as is and `images` are files in the
+    /// mock server uploaded as `ATTACHMENT_ID`, `ATTACHMENT_ID + 1`...
+    pub fn command(&self, name: &str, options: Value, images: &[&str]) -> Interaction {
+        let attachments: serde_json::Map<_, _> = images
+            .iter()
+            .zip(ATTACHMENT_ID..)
+            .map(|(file, id)| (id.to_string(), self.attachment(id, file)))
+            .collect();
+        self.interaction(
+            2,
+            json!({
+                "id": "1",
+                "name": name,
+                "type": 1,
+                "options": options,
+                "resolved": { "attachments": attachments }
+            }),
+        )
+    }
+
+    /// The user typing in a command option, the one being written has `"focused": true`.
+    pub fn autocomplete(&self, name: &str, options: Value) -> Interaction {
+        self.interaction(
+            4,
+            json!({ "id": "1", "name": name, "type": 1, "options": options }),
+        )
+    }
+
+    /// A message command used on `message`, which gets filled in on top of [`message_json`].
+    pub fn message_command(&self, name: &str, message: Value) -> Interaction {
+        let mut target = message_json();
+        target
+            .as_object_mut()
+            .unwrap()
+            .extend(message.as_object().unwrap().clone());
+        self.interaction(
+            2,
+            json!({
+                "id": "1",
+                "name": name,
+                "type": 3,
+                "target_id": MESSAGE_ID.to_string(),
+                "resolved": { "messages": { MESSAGE_ID.to_string(): target } }
+            }),
+        )
+    }
+
+    /// Presses a button of the bot's message and returns the callback it got answered with.
+    pub async fn click(&self, custom_id: &str) -> DiscordRequest {
+        self.use_component(json!({ "custom_id": custom_id, "component_type": 2, "values": [] }))
+            .await
+    }
+
+    /// Chooses `values` in a select menu of the bot's message, like [`Harness::click`].
+    pub async fn select(&self, custom_id: &str, values: &[&str]) -> DiscordRequest {
+        self.use_component(json!({ "custom_id": custom_id, "component_type": 3, "values": values }))
+            .await
+    }
+
+    async fn use_component(&self, data: Value) -> DiscordRequest {
+        for _ in 0..CLICK_ATTEMPTS {
+            let mut interaction = self.interaction(3, data.clone());
+            interaction.message = Some(serde_json::from_value(message_json()).unwrap());
+            let callback = format!("/interactions/{}/", interaction.id);
+            self.send(interaction).await.unwrap().unwrap();
+
+            let answer = self
+                .discord
+                .next_within(Duration::from_millis(250), |x| {
+                    x.is_callback() && x.path.starts_with(&callback)
+                })
+                .await;
+            if let Some(answer) = answer {
+                return answer;
+            }
+        }
+        panic!("Nobody answered the {} component", data["custom_id"]);
+    }
+
+    /// Base URL of the mock server, what `{{mock}}` becomes in the fixtures.
+    pub fn mock_url(&self) -> String {
+        format!("http://{}", self.info.config.mock.as_ref().unwrap().addr)
+    }
+
+    fn attachment(&self, id: u64, file: &str) -> Value {
+        let url = format!("{}/files/{file}", self.mock_url());
+        json!({
+            "id": id.to_string(),
+            "filename": file,
+            "size": 0,
+            "url": url,
+            "proxy_url": url,
+            "content_type": "image/png"
+        })
+    }
+
+    fn interaction(&self, kind: u8, data: Value) -> Interaction {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        serde_json::from_value(json!({
+            "id": id.to_string(),
+            "application_id": APPLICATION_ID.to_string(),
+            "type": kind,
+            "token": format!("token-{id}"),
+            "locale": "en-US",
+            "channel": { "id": CHANNEL_ID.to_string(), "type": 1 },
+            "channel_id": CHANNEL_ID.to_string(),
+            "user": {
+                "id": USER_ID.to_string(),
+                "username": "tester",
+                "discriminator": "0001",
+                "avatar": null
+            },
+            "data": data,
+            "version": 1
+        }))
+        .unwrap()
+    }
+}
@@ -0,0 +1,115 @@
+use std::env;
+
+use serde_json::json;
+
+use super::{discord::DiscordRequest, Harness};
+
+/// The themes live in MySQL, so these only run when `TEST_DATABASE_URL` points to a
+/// database with the AnimeThemes dump loaded.
+async fn harness() -> Option<Harness> {
+    if let Ok(url) = env::var("TEST_DATABASE_URL") {
+        Some(Harness::with_config(|config| config.database.url = url).await)
+    } else {
+        eprintln!("TEST_DATABASE_URL isn't set, skipping");
+        None
+    }
+}
+
+#[tokio::test]
+async fn op_not_found() {
+    let harness = if let Some(harness) = harness().await {
+        harness
+    } else {
+        return;
+    };
+    let command = harness.command(
+        "op",
+        json!([{ "name": "theme", "type": 3, "value": "there's no anime called like this" }]),
+        &[],
+    );
+    harness.send(command).await.unwrap().unwrap();
+
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.body["type"], 4);
+    assert_eq!(reply.flags(), 1 << 6);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("Couldn't find the anime theme"));
+}
+
+#[tokio::test]
+async fn op_autocompletes_theme_ids() {
+    let harness = if let Some(harness) = harness().await {
+        harness
+    } else {
+        return;
+    };
+    let interaction = harness.autocomplete(
+        "op",
+        json!([{ "name": "theme", "type": 3, "value": "OP1", "focused": true }]),
+    );
+    harness.send(interaction).await.unwrap().unwrap();
+
+    let response = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(response.body["type"], 8);
+    let choices = response.body["data"]["choices"].as_array().unwrap();
+    assert!(choices.len() <= 25);
+    // Picking a suggestion sends the theme id after a null character
+    assert!(choices
+        .iter()
+        .all(|x| x["value"].as_str().unwrap().starts_with('\0')));
+}
+
+#[tokio::test]
+async fn op_sends_the_theme_link() {
+    let harness = if let Some(harness) = harness().await {
+        harness
+    } else {
+        return;
+    };
+    let interaction = harness.autocomplete(
+        "op",
+        json!([{ "name": "theme", "type": 3, "value": "OP1", "focused": true }]),
+    );
+    harness.send(interaction).await.unwrap().unwrap();
+    let response = harness.discord.next(DiscordRequest::is_callback).await;
+    let theme = if let Some(theme) = response.body["data"]["choices"][0]["value"].as_str() {
+        theme.to_string()
+    } else {
+        eprintln!("The database has no themes, skipping");
+        return;
+    };
+
+    let command = harness.command(
+        "op",
+        json!([{ "name": "theme", "type": 3, "value": theme }]),
+        &[],
+    );
+    harness.send(command).await.unwrap().unwrap();
+
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.body["type"], 4);
+    let content = reply.content().unwrap();
+    assert!(
+        content.contains("https://animethemes.moe/anime/")
+            || content.starts_with("This theme is yet to be uploaded"),
+        "{content}"
+    );
+}
+
+#[tokio::test]
+async fn op_answers_the_lookup_buttons() {
+    let harness = if let Some(harness) = harness().await {
+        harness
+    } else {
+        return;
+    };
+    // Like the one in the trace.moe results
+    let reply = harness.click("op:there's no anime called like this").await;
+    assert_eq!(reply.body["type"], 4);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("Couldn't find the anime theme"));
+}
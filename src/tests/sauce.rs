@@ -0,0 +1,588 @@
+use std::{fs, time::Duration};
+
+use serde_json::{json, Value};
+use tokio::time::timeout;
+use unic_langid::langid;
+
+use super::{
+    discord::{choices, fields, DiscordRequest},
+    Harness, ATTACHMENT_ID,
+};
+use crate::util::{
+    censor::{Censor, CensorMethod, NsfwPolicy},
+    sauce::{rank, Engine, SauceMatch},
+    tracemoe::{build_embed as build_tracemoe_embed, EpisodeField, TraceResponse},
+};
+
+pub fn subcommand(name: &str) -> Value {
+    subcommand_with(name, json!([]))
+}
+
+/// The image option goes first, followed by `extra`.
+fn subcommand_with(name: &str, extra: Value) -> Value {
+    let mut options =
+        vec![json!({ "name": "image", "type": 11, "value": ATTACHMENT_ID.to_string() })];
+    options.extend(extra.as_array().unwrap().iter().cloned());
+    json!([{ "name": name, "type": 1, "options": options }])
+}
+
+#[tokio::test]
+async fn saucenao_sends_the_result() {
+    let harness = Harness::new().await;
+    let command =
+        harness.send(harness.command("sauce", subcommand("saucenao"), &["thumbnail.png"]));
+
+    let deferred = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(deferred.body["type"], 5);
+
+    // A single result doesn't get any buttons
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = followup.embed();
+    assert_eq!(embed["title"], "https://twitter.com/example/status/1");
+    assert_eq!(embed["url"], "https://danbooru.donmai.us/post/show/1234567");
+    assert_eq!(embed["author"]["name"], "example");
+    assert_eq!(embed["description"], "Similarity 93.12%");
+    assert_eq!(embed["footer"]["text"], "Index #9: Danbooru - 1234567.jpg");
+    assert_eq!(embed["image"]["url"], "attachment://saucenao.png");
+    assert_eq!(embed["color"], 0x9b59b6);
+    assert_eq!(followup.files, ["saucenao.png"]);
+    assert_eq!(followup.buttons(), None);
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn saucenao_shows_the_filters() {
+    let harness = Harness::new().await;
+    let options = json!([
+        { "name": "database", "type": 3, "value": "booru" },
+        { "name": "similarity", "type": 10, "value": 90.5 }
+    ]);
+    let command = harness.send(harness.command(
+        "sauce",
+        subcommand_with("saucenao", options),
+        &["thumbnail.png"],
+    ));
+
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(
+        followup.embed()["footer"]["text"],
+        "Index #9: Danbooru - 1234567.jpg · Boorus · 90.5% similar or more"
+    );
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn saucenao_drops_less_similar_results() {
+    let harness = Harness::new().await;
+    let options = json!([{ "name": "similarity", "type": 10, "value": 95 }]);
+    let command = harness.send(harness.command(
+        "sauce",
+        subcommand_with("saucenao", options),
+        &["thumbnail.png"],
+    ));
+
+    // The only result is 93.12% similar
+    let reply = harness.discord.next(DiscordRequest::is_edit).await;
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("None of the results are at least 95% similar"));
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn saucenao_without_token_is_disabled() {
+    let harness = Harness::with_config(|config| config.saucenao = None).await;
+    let command = harness.command("sauce", subcommand("saucenao"), &["thumbnail.png"]);
+    harness.send(command).await.unwrap().unwrap();
+
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.body["type"], 4);
+    assert_eq!(reply.flags(), 1 << 6);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("SauceNAO searches aren't set up in this bot"));
+}
+
+#[tokio::test]
+async fn saucenao_tracks_the_limits() {
+    let harness = Harness::new().await;
+    let command = harness.command("sauce", subcommand("saucenao"), &["thumbnail.png"]);
+    harness.send(command).await.unwrap().unwrap();
+
+    let metrics = harness.info.saucenao_limits.metrics();
+    assert!(metrics.contains("saucenao_short_limit 4\n"));
+    assert!(metrics.contains("saucenao_short_remaining 3\n"));
+    assert!(metrics.contains("saucenao_long_remaining 99\n"));
+    assert!(metrics.contains("saucenao_refused_total 0\n"));
+}
+
+#[tokio::test]
+async fn saucenao_rate_limited_refuses_for_the_rest_of_the_window() {
+    let harness = Harness::with_config(|config| {
+        config.saucenao.as_mut().unwrap().token = "test-ratelimited".to_string();
+        config.http.retries = 3;
+    })
+    .await;
+
+    // SauceNAO refuses the first one, which isn't retried since that spends more searches
+    let command = harness.command("sauce", subcommand("saucenao"), &["thumbnail.png"]);
+    timeout(Duration::from_secs(3), harness.send(command))
+        .await
+        .expect("The 429 was retried")
+        .unwrap()
+        .unwrap();
+    let reply = harness.discord.next(DiscordRequest::is_edit).await;
+    let content = reply.content().unwrap();
+    assert!(content.starts_with("Seems that SauceNAO is not having a good time currently"));
+    assert!(content.contains("try again <t:"));
+
+    // And the next one doesn't even get to ask for the rest of the window
+    let command = harness.command("sauce", subcommand("saucenao"), &["thumbnail.png"]);
+    harness.send(command).await.unwrap().unwrap();
+    let reply = harness.discord.next(DiscordRequest::is_edit).await;
+    assert!(reply.content().unwrap().contains("try again <t:"));
+    assert!(harness
+        .info
+        .saucenao_limits
+        .metrics()
+        .contains("saucenao_refused_total 1\n"));
+}
+
+#[tokio::test]
+async fn saucenao_searches_a_link() {
+    let harness = Harness::new().await;
+    let url = format!("{}/files/thumbnail.png", harness.mock_url());
+    let options = json!([{
+        "name": "saucenao",
+        "type": 1,
+        "options": [{ "name": "url", "type": 3, "value": url }]
+    }]);
+    let command = harness.send(harness.command("sauce", options, &[]));
+
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(followup.content(), None);
+    assert_eq!(
+        followup.embed()["title"],
+        "https://twitter.com/example/status/1"
+    );
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn saucenao_groups_every_image() {
+    let harness = Harness::new().await;
+    let options = json!([
+        { "name": "image2", "type": 11, "value": (ATTACHMENT_ID + 1).to_string() }
+    ]);
+    let command = harness.send(harness.command(
+        "sauce",
+        subcommand_with("saucenao", options),
+        &["thumbnail.png", "gradient.png"],
+    ));
+
+    let first = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(first.content(), Some("Image 1 of 2: `thumbnail.png`"));
+    assert_eq!(
+        first.buttons().unwrap(),
+        [("back".to_string(), true), ("next".to_string(), false)]
+    );
+
+    harness.click("next").await;
+    let second = harness.discord.next(DiscordRequest::is_edit).await;
+    assert_eq!(second.content(), Some("Image 2 of 2: `gradient.png`"));
+    assert_eq!(
+        second.embed()["title"],
+        "https://twitter.com/example/status/1"
+    );
+
+    harness.info.shutdown.cancel();
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn sauce_refuses_bad_links() {
+    let harness = Harness::new().await;
+    for url in ["file:///etc/passwd", "not a link"] {
+        let options = json!([{
+            "name": "tracemoe",
+            "type": 1,
+            "options": [{ "name": "url", "type": 3, "value": url }]
+        }]);
+        harness
+            .send(harness.command("sauce", options, &[]))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let reply = harness.discord.next(DiscordRequest::is_callback).await;
+        assert_eq!(reply.flags(), 64);
+        assert!(reply
+            .content()
+            .unwrap()
+            .starts_with("That link can't be searched"));
+    }
+}
+
+#[tokio::test]
+async fn sauce_needs_an_image() {
+    let harness = Harness::new().await;
+    let options = json!([{ "name": "saucenao", "type": 1, "options": [] }]);
+    harness
+        .send(harness.command("sauce", options, &[]))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let reply = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(reply.flags(), 64);
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("Send an image or a link to one"));
+}
+
+#[tokio::test]
+async fn tracemoe_sends_the_scene() {
+    let harness = Harness::new().await;
+    let command =
+        harness.send(harness.command("sauce", subcommand("tracemoe"), &["thumbnail.png"]));
+
+    let deferred = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(deferred.body["type"], 5);
+
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = followup.embed();
+    assert_eq!(embed["title"], "ONE PIECE");
+    assert_eq!(embed["url"], "https://anilist.co/anime/21/");
+    assert_eq!(embed["image"]["url"], "attachment://trace.png");
+    assert_eq!(
+        fields(embed),
+        [
+            ("Similarity:", "94.40%"),
+            ("Timestamp:", "Episode 1 at 11:03–11:05"),
+            ("Also known as:", "OP"),
+            (
+                "Links:",
+                "[AniList](https://anilist.co/anime/21/) · \
+                 [MyAnimeList](https://myanimelist.net/anime/21/)"
+            )
+        ]
+    );
+    // Not an adult anime, so it isn't censored
+    assert!(embed.get("description").is_none());
+    assert_eq!(followup.files, ["trace.png"]);
+    // Looks up its openings with /op
+    assert_eq!(
+        followup.buttons().unwrap(),
+        [("op:ONE PIECE".to_string(), false)]
+    );
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn tracemoe_attaches_the_clip() {
+    let harness = Harness::new().await;
+    let options = json!([{ "name": "clip", "type": 5, "value": true }]);
+    let command = harness.send(harness.command(
+        "sauce",
+        subcommand_with("tracemoe", options),
+        &["thumbnail.png"],
+    ));
+
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = followup.embed();
+    assert_eq!(embed["title"], "ONE PIECE");
+    // The clip shows up above the embed instead
+    assert!(embed.get("image").is_none());
+    assert_eq!(followup.files, ["trace.mp4"]);
+    command.await.unwrap().unwrap();
+}
+
+/// The recorded trace.moe response with `edit` applied to its best result.
+fn trace_response(harness: &Harness, edit: impl FnOnce(&mut Value)) -> TraceResponse {
+    let text = fs::read_to_string("fixtures/mock/tracemoe.json")
+        .unwrap()
+        .replace("{{mock}}", &harness.mock_url());
+    let fixtures: Value = serde_json::from_str(&text).unwrap();
+    let mut body = fixtures[0]["body"].clone();
+    edit(&mut body["result"][0]);
+    serde_json::from_value(body).unwrap()
+}
+
+#[tokio::test]
+async fn tracemoe_keeps_adult_clips_out_of_sfw_channels() {
+    let harness = Harness::new().await;
+    let res = trace_response(&harness, |result| {
+        result["anilist"]["isAdult"] = json!(true)
+    });
+    let data = &res.result[0];
+
+    let lang = langid!("en-US");
+    let censor = Censor::new(CensorMethod::Blur, 80).unwrap();
+    let (embed, attachment) =
+        build_tracemoe_embed(&harness.info.web, data, false, censor, &res, true, &lang)
+            .await
+            .unwrap();
+    assert_eq!(attachment.unwrap().filename, "trace.png");
+    assert_eq!(
+        embed.build().description.unwrap(),
+        "**WARNING**: Image is NSFW so it's been censored!\n\
+         The clip can only be sent in NSFW channels"
+    );
+
+    // But they are fine in NSFW ones
+    let (_, attachment) =
+        build_tracemoe_embed(&harness.info.web, data, true, censor, &res, true, &lang)
+            .await
+            .unwrap();
+    assert_eq!(attachment.unwrap().filename, "trace.mp4");
+}
+
+#[tokio::test]
+async fn tracemoe_follows_the_nsfw_policy() {
+    let harness = Harness::new().await;
+    let res = trace_response(&harness, |result| {
+        result["anilist"]["isAdult"] = json!(true)
+    });
+    let data = &res.result[0];
+    let lang = langid!("en-US");
+
+    let cases = [
+        (NsfwPolicy::Hide, false, None, false),
+        (NsfwPolicy::Spoiler, false, Some("SPOILER_trace.png"), false),
+        // Clips can't be censored but they can go behind a spoiler
+        (NsfwPolicy::Spoiler, true, Some("SPOILER_trace.mp4"), false),
+        (NsfwPolicy::Allow, true, Some("trace.mp4"), false),
+        (NsfwPolicy::Allow, false, Some("trace.png"), true),
+    ];
+    for (policy, clip, file, embedded) in cases {
+        let censor = Censor {
+            policy,
+            ..Censor::default()
+        };
+        let (embed, attachment) =
+            build_tracemoe_embed(&harness.info.web, data, false, censor, &res, clip, &lang)
+                .await
+                .unwrap();
+        let embed = embed.build();
+        assert_eq!(
+            attachment.map(|x| x.filename).as_deref(),
+            file,
+            "{policy:?}"
+        );
+        assert_eq!(embed.image.is_some(), embedded, "{policy:?}");
+        let description = embed.description.unwrap_or_default();
+        match policy {
+            NsfwPolicy::Hide => assert!(description.contains("it's been left out")),
+            NsfwPolicy::Spoiler => assert!(description.contains("behind a spoiler")),
+            _ => assert_eq!(description, ""),
+        }
+    }
+}
+
+#[tokio::test]
+async fn tracemoe_shows_other_titles_and_episode_ranges() {
+    let harness = Harness::new().await;
+    let res = trace_response(&harness, |result| {
+        result["anilist"] = json!({
+            "id": 1,
+            "idMal": null,
+            "title": { "native": "カウボーイビバップ", "romaji": "Cowboy Bebop", "english": null },
+            "synonyms": ["Cowboy Bebop", "CB"],
+            "isAdult": false
+        });
+        result["episode"] = json!([1, 2]);
+    });
+
+    let lang = langid!("en-US");
+    let (embed, _) = build_tracemoe_embed(
+        &harness.info.web,
+        &res.result[0],
+        false,
+        Censor::default(),
+        &res,
+        false,
+        &lang,
+    )
+    .await
+    .unwrap();
+    let embed = serde_json::to_value(embed.build()).unwrap();
+    assert_eq!(embed["title"], "Cowboy Bebop");
+    // The title isn't repeated and there's no MyAnimeList link without its id
+    assert_eq!(
+        fields(&embed),
+        [
+            ("Similarity:", "94.40%"),
+            ("Timestamp:", "Episodes 1–2 at 11:03–11:05"),
+            ("Also known as:", "カウボーイビバップ\nCB")
+        ]
+    );
+}
+
+#[test]
+fn episode_ranges_read_as_a_span() {
+    assert_eq!(EpisodeField::Range(vec![3.0, 4.0, 5.0]).to_string(), "3–5");
+    assert_eq!(EpisodeField::Range(Vec::new()).to_string(), "");
+}
+
+#[tokio::test]
+async fn tracemoe_searches_links_by_url() {
+    let harness = Harness::new().await;
+    let url = format!("{}/files/gradient-small.png", harness.mock_url());
+    let options = json!([{
+        "name": "tracemoe",
+        "type": 1,
+        "options": [{ "name": "url", "type": 3, "value": url }]
+    }]);
+    let command = harness.send(harness.command("sauce", options, &[]));
+
+    // trace.moe only answers about it when it's asked with the link
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    let embed = followup.embed();
+    assert_eq!(embed["title"], "Cowboy Bebop");
+    assert_eq!(embed["footer"]["text"], "958 searches left");
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn tracemoe_explains_its_errors() {
+    let harness = Harness::new().await;
+    let url = format!("{}/files/clip.mp4", harness.mock_url());
+    let options = json!([{
+        "name": "tracemoe",
+        "type": 1,
+        "options": [{ "name": "url", "type": 3, "value": url }]
+    }]);
+    let command = harness.send(harness.command("sauce", options, &[]));
+
+    let reply = harness.discord.next(DiscordRequest::is_edit).await;
+    assert!(reply
+        .content()
+        .unwrap()
+        .starts_with("trace.moe couldn't search the image: Failed to process image"));
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn tracemoe_autocompletes_anime() {
+    let harness = Harness::new().await;
+    let options = json!([{
+        "name": "tracemoe",
+        "type": 1,
+        "options": [{ "name": "anime", "type": 3, "value": "bebop", "focused": true }]
+    }]);
+    harness
+        .send(harness.autocomplete("sauce", options))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let response = harness.discord.next(DiscordRequest::is_callback).await;
+    assert_eq!(response.body["type"], 8);
+    assert_eq!(
+        choices(&response),
+        [("Cowboy Bebop", "\01"), ("Cowboy Bebop: The Movie", "\05")]
+    );
+}
+
+#[tokio::test]
+async fn tracemoe_filters_by_anime() {
+    let harness = Harness::new().await;
+    // A suggestion and a title that has to be looked up
+    for anime in ["\01", "bebop"] {
+        let options = json!([{ "name": "anime", "type": 3, "value": anime }]);
+        let command = harness.send(harness.command(
+            "sauce",
+            subcommand_with("tracemoe", options),
+            &["thumbnail.png"],
+        ));
+
+        // Before AniList gets asked
+        let deferred = harness.discord.next(DiscordRequest::is_callback).await;
+        assert_eq!(deferred.body["type"], 5);
+        let followup = harness.discord.next(DiscordRequest::is_followup).await;
+        assert_eq!(followup.embed()["title"], "Cowboy Bebop");
+        command.await.unwrap().unwrap();
+    }
+}
+
+fn found(engine: Engine, similarity: f64, sources: &[&str]) -> SauceMatch {
+    SauceMatch {
+        engine,
+        index: 0,
+        similarity,
+        sources: sources.iter().map(|x| x.to_string()).collect(),
+    }
+}
+
+#[test]
+fn auto_keeps_the_best_of_each_source() {
+    let ranked = rank(vec![
+        found(Engine::SauceNao, 60.0, &["danbooru.donmai.us/post/show/1"]),
+        found(Engine::TraceMoe, 80.0, &["anilist.co/anime/21"]),
+        found(
+            Engine::SauceNao,
+            90.0,
+            &["anidb.net/anime/69", "anilist.co/anime/21"],
+        ),
+        found(Engine::TraceMoe, 70.0, &["anilist.co/anime/1"]),
+    ]);
+    let ranked = ranked
+        .iter()
+        .map(|x| (x.engine, x.similarity))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ranked,
+        [
+            (Engine::SauceNao, 90.0),
+            (Engine::TraceMoe, 70.0),
+            (Engine::SauceNao, 60.0)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn auto_merges_every_engine() {
+    let harness = Harness::new().await;
+    let command = harness.send(harness.command("sauce", subcommand("auto"), &["thumbnail.png"]));
+
+    // trace.moe's 94.40% goes before SauceNAO's 93.12%
+    let first = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(first.embed()["title"], "ONE PIECE");
+    assert_eq!(first.embed()["footer"]["text"], "trace.moe");
+    assert_eq!(
+        first.buttons().unwrap(),
+        [("back".to_string(), true), ("next".to_string(), false)]
+    );
+
+    harness.click("next").await;
+    let second = harness.discord.next(DiscordRequest::is_edit).await;
+    assert_eq!(
+        second.embed()["title"],
+        "https://twitter.com/example/status/1"
+    );
+    assert_eq!(
+        second.embed()["footer"]["text"],
+        "SauceNAO · Index #9: Danbooru - 1234567.jpg"
+    );
+    assert_eq!(
+        second.buttons().unwrap(),
+        [("back".to_string(), false), ("next".to_string(), true)]
+    );
+
+    harness.info.shutdown.cancel();
+    command.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn auto_without_saucenao_uses_the_rest() {
+    let harness = Harness::with_config(|config| config.saucenao = None).await;
+    let command = harness.send(harness.command("sauce", subcommand("auto"), &["thumbnail.png"]));
+
+    let followup = harness.discord.next(DiscordRequest::is_followup).await;
+    assert_eq!(followup.embed()["footer"]["text"], "trace.moe");
+    assert_eq!(followup.buttons(), None);
+    command.await.unwrap().unwrap();
+}
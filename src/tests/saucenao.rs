@@ -0,0 +1,206 @@
+use std::{fs, time::Duration};
+
+use unic_langid::langid;
+
+use super::Harness;
+use crate::util::{
+    censor::{Censor, NsfwPolicy},
+    saucenao::{build_embed, Data, DataHeader, RateLimits, ResData, SearchOptions},
+};
+
+/// Recorded SauceNAO responses, one per index.
+const CORPUS: &str = "fixtures/saucenao";
+
+fn load(name: &str, mock_url: &str) -> Data {
+    let path = format!("{CORPUS}/{name}.json");
+    let text = fs::read_to_string(&path)
+        .unwrap()
+        .replace("{{mock}}", mock_url);
+    serde_json::from_str(&text).unwrap_or_else(|err| panic!("{path}: {err}"))
+}
+
+fn corpus() -> Vec<(&'static str, fn(&ResData) -> bool)> {
+    vec![
+        ("hmagazines", |x| matches!(x, ResData::HMagazines(_))),
+        ("pixiv", |x| matches!(x, ResData::Pixiv(_))),
+        ("niconico", |x| matches!(x, ResData::NicoNico(_))),
+        ("danbooru", |x| matches!(x, ResData::Danbooru(_))),
+        ("drawr", |x| matches!(x, ResData::Drawr(_))),
+        ("nijie", |x| matches!(x, ResData::NijieImages(_))),
+        ("yandere", |x| matches!(x, ResData::Yandere(_))),
+        ("fakku", |x| matches!(x, ResData::FAKKU(_))),
+        ("ehentai", |x| matches!(x, ResData::EHentai(_))),
+        ("2dmarket", |x| matches!(x, ResData::Market2D(_))),
+        ("medibang", |x| matches!(x, ResData::MediBang(_))),
+        ("anime", |x| matches!(x, ResData::Anime(_))),
+        ("movies", |x| matches!(x, ResData::Movies(_))),
+        ("shows", |x| matches!(x, ResData::Shows(_))),
+        ("gelbooru", |x| matches!(x, ResData::Gelbooru(_))),
+        ("konachan", |x| matches!(x, ResData::Konachan(_))),
+        ("sankaku", |x| matches!(x, ResData::Sankaku(_))),
+        ("animepictures", |x| matches!(x, ResData::AnimePictures(_))),
+        ("e621", |x| matches!(x, ResData::E621(_))),
+        ("idolcomplex", |x| matches!(x, ResData::IdolComplex(_))),
+        ("bcy", |x| matches!(x, ResData::Bcy(_))),
+        ("bcycoser", |x| matches!(x, ResData::BcyCoser(_))),
+        ("deviantart", |x| matches!(x, ResData::DeviantArt(_))),
+        ("pawoo", |x| matches!(x, ResData::Pawoo(_))),
+        ("madokami", |x| matches!(x, ResData::Madokami(_))),
+        ("mangadex", |x| matches!(x, ResData::Mangadex(_))),
+        ("artstation", |x| matches!(x, ResData::Artstation(_))),
+        ("furaffinity", |x| matches!(x, ResData::FurAffinity(_))),
+        ("twitter", |x| matches!(x, ResData::Twitter(_))),
+        ("furrynetwork", |x| matches!(x, ResData::FurryNetwork(_))),
+        ("kemono", |x| matches!(x, ResData::Kemono(_))),
+        ("skeb", |x| matches!(x, ResData::Skeb(_))),
+        ("unknown-index", |x| matches!(x, ResData::Unknown { .. })),
+        ("schema-drift", |x| matches!(x, ResData::Unknown { .. })),
+    ]
+}
+
+#[test]
+fn every_index_gets_its_variant() {
+    for (name, is_variant) in corpus() {
+        let data = load(name, "http://mock");
+        assert_eq!(data.results.len(), 1, "{name}");
+        assert!(
+            is_variant(&data.results[0].data),
+            "{name}: {:#?}",
+            data.results[0].data
+        );
+    }
+}
+
+#[test]
+fn every_recording_is_checked() {
+    let names: Vec<_> = corpus().into_iter().map(|(name, _)| name).collect();
+    for entry in fs::read_dir(CORPUS).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        assert!(
+            names.contains(&name.as_str()),
+            "{name} isn't in the corpus list"
+        );
+    }
+}
+
+#[test]
+fn unknown_keeps_the_raw_data() {
+    let data = load("unknown-index", "http://mock");
+    match &data.results[0].data {
+        ResData::Unknown {
+            index_name,
+            ext_urls,
+            raw,
+        } => {
+            assert_eq!(index_name, "Index #999: Some New Site - 1234.jpg");
+            assert_eq!(ext_urls, &["https://example.com/posts/1234"]);
+            assert_eq!(raw["new_site_id"], 1234);
+        }
+        data => panic!("Expected an unknown result, got {data:#?}"),
+    }
+}
+
+#[test]
+fn limits_come_as_strings_or_numbers() {
+    let header: DataHeader = serde_json::from_str(
+        r#"{ "status": 0, "short_limit": "4", "long_limit": 100, "short_remaining": 3 }"#,
+    )
+    .unwrap();
+    assert_eq!(header.short_limit, Some(4));
+    assert_eq!(header.long_limit, Some(100));
+    assert_eq!(header.short_remaining, Some(3));
+    assert_eq!(header.long_remaining, None);
+}
+
+#[test]
+fn limits_stop_at_the_last_search() {
+    let limits = RateLimits::default();
+    // Nothing is known before the first search
+    assert_eq!(limits.try_take(), Ok(()));
+
+    let header: DataHeader =
+        serde_json::from_str(r#"{ "status": 0, "short_limit": "4", "short_remaining": 1 }"#)
+            .unwrap();
+    limits.update(&header);
+    assert_eq!(limits.try_take(), Ok(()));
+    let wait = limits.try_take().unwrap_err();
+    assert!(wait <= Duration::from_secs(30));
+    assert!(limits.retry_after().is_some());
+}
+
+#[test]
+fn refusals_use_up_the_window() {
+    let limits = RateLimits::default();
+    let header: DataHeader =
+        serde_json::from_str(r#"{ "status": -2, "message": "Daily Search Limit Exceeded." }"#)
+            .unwrap();
+    limits.update(&header);
+    // Way longer than the short window
+    assert!(limits.retry_after().unwrap() > Duration::from_secs(60 * 60));
+}
+
+#[test]
+fn odd_result_keeps_the_rest() {
+    let pixiv = load("pixiv", "http://mock");
+    let mut data = load("schema-drift", "http://mock");
+    data.results.insert(0, pixiv.results[0].clone());
+    assert!(matches!(data.results[0].data, ResData::Pixiv(_)));
+    assert!(matches!(data.results[1].data, ResData::Unknown { .. }));
+}
+
+#[tokio::test]
+async fn every_index_builds_an_embed() {
+    let harness = Harness::new().await;
+    let lang = langid!("en-US");
+    for (name, _) in corpus() {
+        let data = load(name, &harness.mock_url());
+        let res = &data.results[0];
+        let (embed, _) = build_embed(
+            &harness.info.web,
+            res,
+            &SearchOptions::default(),
+            false,
+            &lang,
+        )
+        .await
+        .unwrap_or_else(|err| panic!("{name}: {err:?}"));
+        let embed = embed.build();
+        assert_eq!(
+            embed.footer.map(|x| x.text),
+            Some(res.header.index_name.clone()),
+            "{name}"
+        );
+        assert!(embed.title.is_some(), "{name} has no title");
+        // Hidden results get censored outside of NSFW channels
+        assert_eq!(
+            embed.description.unwrap().contains("NSFW"),
+            res.header.hidden != 0,
+            "{name}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn adult_sites_can_count_as_nsfw() {
+    let harness = Harness::new().await;
+    let lang = langid!("en-US");
+    let options = SearchOptions {
+        censor: Censor {
+            policy: NsfwPolicy::Hide,
+            nsfw_sites: true,
+            ..Censor::default()
+        },
+        ..SearchOptions::default()
+    };
+    for (name, _) in corpus() {
+        let data = load(name, &harness.mock_url());
+        let res = &data.results[0];
+        let (embed, attachment) = build_embed(&harness.info.web, res, &options, false, &lang)
+            .await
+            .unwrap_or_else(|err| panic!("{name}: {err:?}"));
+        let nsfw = res.header.hidden != 0 || res.data.is_nsfw_site();
+        assert_eq!(attachment.is_none(), nsfw, "{name}");
+        assert_eq!(embed.build().image.is_none(), nsfw, "{name}");
+    }
+}
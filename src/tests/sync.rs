@@ -0,0 +1,90 @@
+use serde_json::json;
+use twilight_model::application::command::{Command, CommandType};
+use twilight_util::builder::command::{
+    BooleanBuilder, CommandBuilder, NumberBuilder, StringBuilder,
+};
+
+use crate::interaction::sync::CommandDiff;
+
+/// Built the same way the derived commands are, with the options that aren't required
+/// saying so explicitly.
+fn wanted(description: &str) -> Command {
+    CommandBuilder::new("echo", description, CommandType::ChatInput)
+        .name_localizations([("es-ES", "eco")])
+        .description_localizations([("es-ES", "Repite lo que decis")])
+        .option(
+            StringBuilder::new("text", "What to repeat")
+                .required(true)
+                .autocomplete(false),
+        )
+        .option(BooleanBuilder::new("loud", "Says it louder").required(false))
+        .option(
+            NumberBuilder::new("speed", "How fast to say it")
+                .min_value(0.0)
+                .required(false),
+        )
+        .build()
+}
+
+/// What Discord answers with for [`wanted`], it leaves out everything that's false and
+/// sends the minimum of the number option as an integer.
+fn live() -> Command {
+    serde_json::from_value(json!({
+        "id": "1100000000000000001",
+        "application_id": "1000",
+        "version": "1100000000000000002",
+        "default_member_permissions": null,
+        "type": 1,
+        "name": "echo",
+        "name_localizations": { "es-ES": "eco" },
+        "description": "Repeats what you say",
+        "description_localizations": { "es-ES": "Repite lo que decis" },
+        "dm_permission": true,
+        "nsfw": false,
+        "options": [
+            {
+                "type": 3,
+                "name": "text",
+                "name_localizations": {},
+                "description": "What to repeat",
+                "description_localizations": {},
+                "required": true
+            },
+            { "type": 5, "name": "loud", "description": "Says it louder" },
+            {
+                "type": 10,
+                "name": "speed",
+                "description": "How fast to say it",
+                "min_value": 0
+            }
+        ]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn unchanged_commands_are_left_alone() {
+    let diff = CommandDiff::new(&[live()], &[wanted("Repeats what you say")]);
+    assert!(diff.is_empty(), "{diff}");
+}
+
+#[test]
+fn changed_commands_get_updated() {
+    let diff = CommandDiff::new(&[live()], &[wanted("Repeats everything you say")]);
+    assert_eq!(diff.updated, ["echo"]);
+    assert!(diff.created.is_empty() && diff.deleted.is_empty());
+}
+
+#[test]
+fn new_commands_get_created() {
+    let diff = CommandDiff::new(&[], &[wanted("Repeats what you say")]);
+    assert_eq!(diff.created, ["echo"]);
+    assert_eq!(diff.to_string(), "created echo");
+}
+
+#[test]
+fn removed_commands_get_deleted() {
+    let diff = CommandDiff::new(&[live()], &[]);
+    assert_eq!(diff.deleted, ["echo"]);
+    assert_eq!(diff.to_string(), "deleted echo");
+}
@@ -0,0 +1,48 @@
+use std::net::IpAddr;
+
+use crate::util::web::is_public_ip;
+
+#[test]
+fn only_public_addresses_can_be_downloaded() {
+    let private = [
+        "0.0.0.0",
+        "10.1.2.3",
+        "100.64.0.1",
+        "127.0.0.1",
+        "169.254.169.254",
+        "172.16.0.1",
+        "192.0.0.8",
+        "192.168.1.1",
+        "198.18.0.1",
+        "203.0.113.7",
+        "224.0.0.1",
+        "255.255.255.255",
+        "::",
+        "::1",
+        "::127.0.0.1",
+        "::ffff:127.0.0.1",
+        "::ffff:10.0.0.1",
+        "64:ff9b::a9fe:a9fe",
+        "2002:7f00:1::",
+        "fc00::1",
+        "fd12:3456::1",
+        "fe80::1",
+        "fec0::1",
+        "ff02::1",
+        "2001:db8::1",
+        "2001::1",
+    ];
+    for ip in private {
+        assert!(!is_public_ip(ip.parse::<IpAddr>().unwrap()), "{ip}");
+    }
+
+    let public = [
+        "1.1.1.1",
+        "93.184.216.34",
+        "::ffff:93.184.216.34",
+        "2606:4700:4700::1111",
+    ];
+    for ip in public {
+        assert!(is_public_ip(ip.parse::<IpAddr>().unwrap()), "{ip}");
+    }
+}
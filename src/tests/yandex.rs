@@ -0,0 +1,17 @@
+use twilight_model::channel::Attachment;
+
+use super::{Harness, ATTACHMENT_ID};
+use crate::util::yandex::fetch;
+
+#[tokio::test]
+async fn finds_the_sites_with_the_image() {
+    let harness = Harness::new().await;
+    let attachment: Attachment =
+        serde_json::from_value(harness.attachment(ATTACHMENT_ID, "thumbnail.png")).unwrap();
+    let response = fetch(&harness.info.web, &attachment).await.unwrap();
+    assert_eq!(response.sites.len(), 1);
+    assert_eq!(
+        response.sites[0].url,
+        "https://danbooru.donmai.us/posts/1234567"
+    );
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    tracemoe::AnilistTitle,
+    web::{Service, WebClient},
+};
+
+const SEARCH_QUERY: &str = "query ($search: String, $perPage: Int) {
+  Page(perPage: $perPage) {
+    media(search: $search, type: ANIME, sort: SEARCH_MATCH) {
+      id
+      title { native romaji english }
+    }
+  }
+}";
+
+/// Anime whose titles match `search`, best match first.
+pub async fn search_anime(web: &WebClient, search: &str, limit: u8) -> Result<Vec<AnilistAnime>> {
+    let request = web.post(web.url(Service::AniList, "/")?).json(&json!({
+        "query": SEARCH_QUERY,
+        "variables": { "search": search, "perPage": limit },
+    }));
+    let res = web
+        .send(request)
+        .await?
+        .error_for_status()?
+        .json::<SearchResponse>()
+        .await?;
+    Ok(res.data.page.media)
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResponse {
+    data: SearchData,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchData {
+    #[serde(rename = "Page")]
+    page: SearchPage,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchPage {
+    media: Vec<AnilistAnime>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnilistAnime {
+    pub id: u32,
+    pub title: AnilistTitle,
+}
+
+impl AnilistAnime {
+    /// The english title if it has one, like trace.moe's embeds.
+    pub fn name(&self) -> &str {
+        self.title.english.as_ref().unwrap_or(&self.title.romaji)
+    }
+}
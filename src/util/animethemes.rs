@@ -1,4 +1,4 @@
-use std::{env, process::Stdio, sync::Arc, borrow::Cow};
+use std::{process::Stdio, sync::Arc, borrow::Cow, time::Duration};
 
 use anyhow::Result;
 use bonsaidb::{core::keyvalue::AsyncKeyValue, local::AsyncDatabase};
@@ -10,12 +10,16 @@ use sqlx::{query_as, MySql, Pool};
 use tokio::{io, process::Command};
 use tokio_util::io::StreamReader;
 
+use super::web::{Service, WebClient};
+
 // FIXME: AnimeThemes now has 2 different dumps on the same API, use the `wiki` one please.
-pub async fn update_database(bonsai: Arc<AsyncDatabase>) -> Result<()> {
-    let client = reqwest::Client::new();
-    let index = client
-        .get("https://api.animethemes.moe/dump/")
-        .send()
+pub async fn update_database(
+    bonsai: Arc<AsyncDatabase>,
+    web: &WebClient,
+    url: &str,
+) -> Result<()> {
+    let index = web
+        .send(web.get(web.url(Service::AnimeThemes, "/dump/")?))
         .await?
         .json::<DumpIndex>()
         .await?;
@@ -31,15 +35,14 @@ pub async fn update_database(bonsai: Arc<AsyncDatabase>) -> Result<()> {
 
     use futures::TryStreamExt;
     let mut reader = StreamReader::new(
-        client
-            .get(last.link.clone())
-            .send()
+        web
+            // The dump is big enough to need more than the usual timeout
+            .send(web.get(&last.link).timeout(Duration::from_secs(600)))
             .await?
             .bytes_stream()
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)),
     );
 
-    let url = env::var("DATABASE_URL").unwrap();
     let (dot, at, slash) = (
         url[8..].find(':').unwrap() + 8,
         url.find('@').unwrap(),
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use reqwest::multipart;
+use scraper::{ElementRef, Html, Selector};
+use sentry::{add_breadcrumb, Breadcrumb, Level};
+use twilight_model::http::attachment::Attachment as HttpAttachment;
+use twilight_util::builder::embed::{EmbedAuthorBuilder, EmbedBuilder, EmbedFooterBuilder};
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    cache::SauceCache,
+    censor::Censor,
+    i18n::get_text,
+    web::{Service, WebClient, MAX_DOWNLOAD_SIZE},
+    SauceImage,
+};
+
+/// Searches in ascii2d by color unless the image is cached, ascii2d has no API so its page
+/// gets scraped.
+pub async fn fetch(
+    web: &WebClient,
+    cache: &SauceCache,
+    image: &SauceImage,
+) -> Result<Ascii2dResponse> {
+    add_breadcrumb(Breadcrumb {
+        category: Some("ascii2d".into()),
+        message: Some(format!("Searching in ascii2d about {} ", image.url)),
+        level: Level::Info,
+        ..Default::default()
+    });
+
+    let bytes = web.get_bytes_limited(&image.url, MAX_DOWNLOAD_SIZE).await?;
+    let hashes = cache.hashes(&bytes).await?;
+    let base = web.url(Service::Ascii2d, "")?;
+    if let Some(html) = cache.get("ascii2d", "color", hashes.as_ref()).await? {
+        let matches = parse(&html, &base).context("The cached ascii2d page has no results")?;
+        return Ok(Ascii2dResponse {
+            matches,
+            cached: true,
+        });
+    }
+
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(bytes.to_vec())
+            .file_name(image.file_name().to_string())
+            .mime_str(image.content_type.as_deref().unwrap_or("image/jpeg"))?,
+    );
+    // It redirects to the color search of the image
+    let request = web
+        .post(web.url(Service::Ascii2d, "/search/file")?)
+        .multipart(form);
+    let html = web.send(request).await?.error_for_status()?.text().await?;
+    let matches = parse(&html, &base).context("ascii2d couldn't search the image")?;
+    if let Err(err) = cache.set("ascii2d", "color", hashes.as_ref(), &html).await {
+        eprintln!("Couldn't cache the ascii2d search: {err}");
+    }
+    Ok(Ascii2dResponse {
+        matches,
+        cached: false,
+    })
+}
+
+#[derive(Debug)]
+pub struct Ascii2dResponse {
+    pub matches: Vec<Ascii2dMatch>,
+    /// Whether it came from the [`SauceCache`] instead of ascii2d
+    pub cached: bool,
+}
+
+/// An item of ascii2d's results that links to where it was posted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ascii2dMatch {
+    pub title: String,
+    pub url: String,
+    /// Name and profile of who posted it
+    pub author: Option<(String, String)>,
+    /// Name of the site it's from
+    pub site: String,
+    pub thumbnail: String,
+    /// Like `1200x1700 JPEG 300.5KB`
+    pub details: Option<String>,
+}
+
+/// The items with a link, the first one is the uploaded image and some only have the
+/// thumbnail. `base` completes the thumbnail paths.
+///
+/// `None` if it isn't a page of results.
+pub fn parse(html: &str, base: &str) -> Option<Vec<Ascii2dMatch>> {
+    let document = Html::parse_document(html);
+    let item_selector = Selector::parse(".item-box").unwrap();
+    let image_selector = Selector::parse(".image-box img").unwrap();
+    let details_selector = Selector::parse(".info-box > small").unwrap();
+    let heading_selector = Selector::parse(".detail-box h6").unwrap();
+    let link_selector = Selector::parse("a").unwrap();
+    let site_selector = Selector::parse("small").unwrap();
+
+    let mut items = document.select(&item_selector).peekable();
+    items.peek()?;
+    let mut matches = Vec::new();
+    for item in items {
+        let Some(heading) = item.select(&heading_selector).next() else {
+            continue;
+        };
+        let mut links = heading.select(&link_selector);
+        // Discord refuses embeds with an empty link
+        let Some((work, url)) = links
+            .next()
+            .and_then(|work| Some((work, work.value().attr("href").filter(|x| !x.is_empty())?)))
+        else {
+            continue;
+        };
+        let Some(thumbnail) = item
+            .select(&image_selector)
+            .next()
+            .and_then(|x| x.value().attr("src"))
+        else {
+            continue;
+        };
+        matches.push(Ascii2dMatch {
+            title: text(work),
+            url: url.to_string(),
+            author: links.next().and_then(|author| {
+                let url = author.value().attr("href").filter(|x| !x.is_empty())?;
+                Some((text(author), url.to_string()))
+            }),
+            site: heading
+                .select(&site_selector)
+                .next()
+                .map(text)
+                .unwrap_or_default(),
+            thumbnail: if thumbnail.starts_with('/') {
+                format!("{}{thumbnail}", base.trim_end_matches('/'))
+            } else {
+                thumbnail.to_string()
+            },
+            details: item.select(&details_selector).next().map(text),
+        });
+    }
+    Some(matches)
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Laid out like [`super::saucenao::build_embed`], the site goes in the footer. ascii2d
+/// doesn't rate its images so outside NSFW channels they all go through the guild's policy.
+pub async fn build_embed(
+    web: &WebClient,
+    data: &Ascii2dMatch,
+    nsfw_channel: bool,
+    censor: Censor,
+    cached: bool,
+    lang: &LanguageIdentifier,
+) -> Result<(EmbedBuilder, Option<HttpAttachment>)> {
+    let image = censor
+        .attach(web, &data.thumbnail, "ascii2d", !nsfw_channel)
+        .await?;
+    let footer = [
+        Some(data.site.clone()),
+        data.details.clone(),
+        cached.then(|| get_text(lang, "sauce-cached", &[])),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|x| !x.is_empty())
+    .collect::<Vec<_>>()
+    .join(" · ");
+    let mut embed = EmbedBuilder::new()
+        .title(&data.title)
+        .url(&data.url)
+        .footer(EmbedFooterBuilder::new(footer))
+        .color(0x4a4a4a);
+    if let Some((name, url)) = &data.author {
+        embed = embed.author(EmbedAuthorBuilder::new(name).url(url));
+    }
+    // The usual warning would claim it's NSFW, which nobody knows
+    if image.warning.is_some() {
+        embed = embed.description(get_text(lang, "sauce-ascii2d-unrated", &[]));
+    }
+    if let Some(source) = image.source()? {
+        embed = embed.image(source);
+    }
+    Ok((embed, image.attachment))
+}
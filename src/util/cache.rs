@@ -0,0 +1,148 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use bonsaidb::{core::keyvalue::AsyncKeyValue, local::AsyncDatabase};
+use bytes::Bytes;
+use image::{imageops::FilterType, GenericImageView};
+use sha2::{Digest, Sha256};
+use tokio::task;
+
+use crate::config::CacheConfig;
+
+/// Bumped to purge, the keys of older generations are never read again and expire on their own
+/// since bonsai can't list or clear keys.
+const GENERATION_KEY: &str = "sauce_cache_generation";
+
+/// Perceptual hashes with fewer bits than this set, or unset, come from flat or low contrast
+/// images that would all look the same.
+const MIN_DETAIL: u32 = 8;
+
+/// Hashes identifying an image, the perceptual one survives resizing and recompression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageHashes {
+    pub content: String,
+    /// `None` if the image couldn't be decoded or doesn't have enough detail
+    pub perceptual: Option<u64>,
+}
+
+impl ImageHashes {
+    pub fn new(bytes: &[u8]) -> Self {
+        ImageHashes {
+            content: format!("{:x}", Sha256::digest(bytes)),
+            perceptual: dhash(bytes),
+        }
+    }
+}
+
+/// Difference hash, a bit per pixel of a 9x8 grayscale copy telling whether it's brighter
+/// than the one to its right.
+fn dhash(bytes: &[u8]) -> Option<u64> {
+    let image = image::load_from_memory(bytes)
+        .ok()?
+        .grayscale()
+        .resize_exact(9, 8, FilterType::Triangle);
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = image.get_pixel(x, y)[0];
+            let right = image.get_pixel(x + 1, y)[0];
+            hash = hash << 1 | u64::from(left > right);
+        }
+    }
+    let detail = hash.count_ones().min(hash.count_zeros());
+    (detail >= MIN_DETAIL).then_some(hash)
+}
+
+/// Responses of the reverse image searches stored in bonsai, so the same image doesn't spend
+/// the quota of the services again.
+#[derive(Clone)]
+pub struct SauceCache {
+    bonsai: Arc<AsyncDatabase>,
+    ttl: Duration,
+    perceptual: bool,
+}
+
+impl SauceCache {
+    pub fn new(bonsai: Arc<AsyncDatabase>, config: &CacheConfig) -> Self {
+        SauceCache {
+            bonsai,
+            ttl: Duration::from_secs(config.ttl),
+            perceptual: config.perceptual,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Hashes the image away from the async executor since it gets decoded,
+    /// `None` when the cache is off and the hashes wouldn't be used.
+    pub async fn hashes(&self, bytes: &Bytes) -> Result<Option<ImageHashes>> {
+        if !self.enabled() {
+            return Ok(None);
+        }
+        let bytes = bytes.clone();
+        Ok(Some(
+            task::spawn_blocking(move || ImageHashes::new(&bytes)).await?,
+        ))
+    }
+
+    async fn keys(&self, engine: &str, params: &str, hashes: &ImageHashes) -> Result<Vec<String>> {
+        let generation = self
+            .bonsai
+            .get_key(GENERATION_KEY)
+            .into_u64()
+            .await?
+            .unwrap_or(0);
+        let prefix = format!("sauce_cache_{generation}_{engine}_{params}");
+        let mut keys = vec![format!("{prefix}_{}", hashes.content)];
+        if let Some(perceptual) = hashes.perceptual.filter(|_| self.perceptual) {
+            keys.push(format!("{prefix}_p{perceptual:016x}"));
+        }
+        Ok(keys)
+    }
+
+    /// The response `engine` gave for the image with the same `params`, the exact image
+    /// goes first and then resized copies.
+    pub async fn get(
+        &self,
+        engine: &str,
+        params: &str,
+        hashes: Option<&ImageHashes>,
+    ) -> Result<Option<String>> {
+        let Some(hashes) = hashes.filter(|_| self.enabled()) else {
+            return Ok(None);
+        };
+        for key in self.keys(engine, params, hashes).await? {
+            if let Some(response) = self.bonsai.get_key(key).into::<String>().await? {
+                return Ok(Some(response));
+            }
+        }
+        Ok(None)
+    }
+
+    pub async fn set(
+        &self,
+        engine: &str,
+        params: &str,
+        hashes: Option<&ImageHashes>,
+        response: &str,
+    ) -> Result<()> {
+        let Some(hashes) = hashes.filter(|_| self.enabled()) else {
+            return Ok(());
+        };
+        for key in self.keys(engine, params, hashes).await? {
+            self.bonsai
+                .set_key(key, &response)
+                .expire_in(self.ttl)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Forgets every response cached so far.
+    pub async fn purge(&self) -> Result<()> {
+        self.bonsai.increment_key_by(GENERATION_KEY, 1u64).await?;
+        Ok(())
+    }
+}
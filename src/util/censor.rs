@@ -0,0 +1,357 @@
+use std::io::Cursor;
+
+use anyhow::{ensure, Context, Result};
+use bytes::Bytes;
+use image::{
+    codecs::{
+        gif::{GifDecoder, GifEncoder, Repeat},
+        webp::WebPDecoder,
+    },
+    imageops::{self, FilterType},
+    io::Reader as ImageReader,
+    AnimationDecoder, DynamicImage, Frame, Frames, ImageFormat, ImageOutputFormat, Rgba, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+use skia_safe::{
+    textlayout::{ParagraphBuilder, ParagraphStyle, TextAlign, TextStyle},
+    Color, EncodedImageFormat, Surface,
+};
+use tokio::task;
+use twilight_interactions::command::{CommandOption, CreateOption};
+use twilight_model::http::attachment::Attachment as HttpAttachment;
+use twilight_util::builder::embed::ImageSource;
+
+use super::{get_font_collection, web::WebClient, FONT_NAMES};
+
+/// Strength used until a guild chooses one, it pixelates as much as before it could be chosen.
+pub const DEFAULT_STRENGTH: u8 = 50;
+/// Discord's dark background, what the overlay covers the image with.
+const OVERLAY_COLOR: [u8; 3] = [0x2c, 0x2f, 0x33];
+const LABEL: &str = "NSFW";
+/// Animations with more frames only get their first one censored.
+const MAX_FRAMES: usize = 100;
+/// Pixels censored at most, adding up every frame. Animations over it only keep their first
+/// frame and images over it get scaled down first.
+const MAX_PIXELS: u64 = 16_000_000;
+
+/// How NSFW images get censored, when that's what the guild does with them.
+#[derive(
+    CommandOption, CreateOption, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CensorMethod {
+    #[default]
+    #[option(name = "Pixelate", value = "pixelate")]
+    Pixelate,
+    #[option(name = "Blur", value = "blur")]
+    Blur,
+    #[option(name = "Cover with a label", value = "overlay")]
+    Overlay,
+}
+
+impl CensorMethod {
+    /// Same as the value of its choice, the settings reply uses it to name the method.
+    pub fn name(self) -> &'static str {
+        match self {
+            CensorMethod::Pixelate => "pixelate",
+            CensorMethod::Blur => "blur",
+            CensorMethod::Overlay => "overlay",
+        }
+    }
+}
+
+/// What happens to NSFW images outside of NSFW channels.
+#[derive(
+    CommandOption, CreateOption, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum NsfwPolicy {
+    #[default]
+    #[option(name = "Censor them", value = "censor")]
+    Censor,
+    #[option(name = "Leave them out", value = "hide")]
+    Hide,
+    #[option(name = "Send them behind a spoiler", value = "spoiler")]
+    Spoiler,
+    #[option(name = "Show them as they are", value = "allow")]
+    Allow,
+}
+
+impl NsfwPolicy {
+    /// Same as the value of its choice, the settings reply uses it to name the policy.
+    pub fn name(self) -> &'static str {
+        match self {
+            NsfwPolicy::Censor => "censor",
+            NsfwPolicy::Hide => "hide",
+            NsfwPolicy::Spoiler => "spoiler",
+            NsfwPolicy::Allow => "allow",
+        }
+    }
+
+    /// Key of what to tell about the image in the embed, if there's anything to tell.
+    pub fn warning(self) -> Option<&'static str> {
+        match self {
+            NsfwPolicy::Censor => Some("sauce-nsfw"),
+            NsfwPolicy::Hide => Some("sauce-nsfw-hidden"),
+            NsfwPolicy::Spoiler => Some("sauce-nsfw-spoiler"),
+            NsfwPolicy::Allow => None,
+        }
+    }
+
+    /// Discord puts the files whose name starts with `SPOILER_` behind a spoiler.
+    pub fn file_name(self, name: &str) -> String {
+        match self {
+            NsfwPolicy::Spoiler => format!("SPOILER_{name}"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// How a guild wants its NSFW images handled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Censor {
+    pub method: CensorMethod,
+    /// Between 1 and 100, the higher the less of the image can be made out
+    pub strength: u8,
+    pub policy: NsfwPolicy,
+    /// Whether everything SauceNAO finds in adult sites is NSFW, even if it says it's safe
+    pub nsfw_sites: bool,
+}
+
+impl Default for Censor {
+    fn default() -> Self {
+        Censor {
+            method: CensorMethod::default(),
+            strength: DEFAULT_STRENGTH,
+            policy: NsfwPolicy::default(),
+            nsfw_sites: false,
+        }
+    }
+}
+
+/// The image of a result, ready to go with its embed.
+pub struct AttachedImage {
+    /// `None` when it's left out
+    pub attachment: Option<HttpAttachment>,
+    /// Whether the embed can show it, embeds can't have spoilers so those go below it
+    pub embedded: bool,
+    /// Key of what to tell about it in the embed
+    pub warning: Option<&'static str>,
+}
+
+impl AttachedImage {
+    /// What the embed shows as its image, `None` if it doesn't show it.
+    pub fn source(&self) -> Result<Option<ImageSource>> {
+        match &self.attachment {
+            Some(attachment) if self.embedded => {
+                Ok(Some(ImageSource::attachment(&attachment.filename)?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Censor {
+    /// Checks the strength, Discord enforces the same limits in the settings already.
+    pub fn new(method: CensorMethod, strength: u8) -> Result<Self> {
+        ensure!(
+            (1..=100).contains(&strength),
+            "{strength} isn't a valid censor strength"
+        );
+        Ok(Censor {
+            method,
+            strength,
+            ..Censor::default()
+        })
+    }
+
+    /// What happens to an image, `nsfw` being whether it's NSFW outside of a NSFW channel.
+    pub fn policy_for(self, nsfw: bool) -> NsfwPolicy {
+        if nsfw {
+            self.policy
+        } else {
+            NsfwPolicy::Allow
+        }
+    }
+
+    /// Downloads the image at `url` unless it's left out and attaches it as `name` with the
+    /// extension of its format, censored or behind a spoiler when `nsfw` and the policy says so.
+    pub async fn attach(
+        self,
+        web: &WebClient,
+        url: &str,
+        name: &str,
+        nsfw: bool,
+    ) -> Result<AttachedImage> {
+        let policy = self.policy_for(nsfw);
+        let image = match policy {
+            NsfwPolicy::Hide => None,
+            NsfwPolicy::Censor => Some(censor_image(web.get_bytes(url).await?, self).await?),
+            NsfwPolicy::Spoiler | NsfwPolicy::Allow => {
+                let image = web.get_bytes(url).await?;
+                // Discord shows them anyway, the extension is only a guess
+                let format = image::guess_format(&image).unwrap_or(ImageFormat::Png);
+                Some((image, format))
+            }
+        };
+        Ok(AttachedImage {
+            attachment: image.map(|(image, format)| {
+                let extension = format.extensions_str().first().unwrap_or(&"png");
+                let name = policy.file_name(&format!("{name}.{extension}"));
+                HttpAttachment::from_bytes(name, image.into(), 1)
+            }),
+            embedded: matches!(policy, NsfwPolicy::Censor | NsfwPolicy::Allow),
+            warning: policy.warning(),
+        })
+    }
+
+    fn apply(self, image: &RgbaImage, label: Option<&RgbaImage>) -> RgbaImage {
+        let (width, height) = image.dimensions();
+        let strength = u32::from(self.strength);
+        match self.method {
+            CensorMethod::Pixelate => {
+                // From 31 blocks across the longest side down to 4, but tiny images still
+                // lose at least half of their pixels
+                let longest = width.max(height);
+                let blocks = (4 + (100 - strength) * 28 / 100).min(longest / 2).max(1);
+                let small = imageops::resize(
+                    image,
+                    (width * blocks / longest).max(1),
+                    (height * blocks / longest).max(1),
+                    FilterType::Triangle,
+                );
+                imageops::resize(&small, width, height, FilterType::Nearest)
+            }
+            CensorMethod::Blur => {
+                let sigma = width.max(height) as f32 * strength as f32 / 1000.0;
+                imageops::blur(image, sigma.max(1.0))
+            }
+            CensorMethod::Overlay => {
+                let [r, g, b] = OVERLAY_COLOR;
+                // Only the strongest one covers it completely
+                let alpha = 128 + strength * 127 / 100;
+                let cover = RgbaImage::from_pixel(width, height, Rgba([r, g, b, alpha as u8]));
+                let mut censored = image.clone();
+                imageops::overlay(&mut censored, &cover, 0, 0);
+                if let Some(label) = label {
+                    imageops::overlay(&mut censored, label, 0, 0);
+                }
+                censored
+            }
+        }
+    }
+}
+
+/// Censors every frame of the image, animated GIFs and WebPs come back as GIFs and
+/// everything else as a PNG. The format it ended up in comes along with it.
+pub async fn censor_image(bytes: Bytes, censor: Censor) -> Result<(Bytes, ImageFormat)> {
+    task::spawn_blocking(move || {
+        let frames = decode_frames(&bytes)?;
+        let (width, height) = frames[0].buffer().dimensions();
+        let label = match censor.method {
+            CensorMethod::Overlay => Some(draw_label(width, height)?),
+            _ => None,
+        };
+
+        let mut new_bytes: Vec<u8> = Vec::new();
+        let format = if frames.len() == 1 {
+            let censored = censor.apply(frames[0].buffer(), label.as_ref());
+            DynamicImage::ImageRgba8(censored)
+                .write_to(&mut Cursor::new(&mut new_bytes), ImageOutputFormat::Png)?;
+            ImageFormat::Png
+        } else {
+            // The GIF isn't finished until the encoder is dropped
+            let mut encoder = GifEncoder::new(&mut new_bytes);
+            encoder.set_repeat(Repeat::Infinite)?;
+            encoder.encode_frames(frames.into_iter().map(|frame| {
+                let censored = censor.apply(frame.buffer(), label.as_ref());
+                Frame::from_parts(censored, frame.left(), frame.top(), frame.delay())
+            }))?;
+            drop(encoder);
+            ImageFormat::Gif
+        };
+        Ok((new_bytes.into(), format))
+    })
+    .await?
+}
+
+/// Every frame of animated GIFs and WebPs, or only the image for the rest. Animations over
+/// the limits keep only their first frame, and a single image over them gets scaled down.
+fn decode_frames(bytes: &[u8]) -> Result<Vec<Frame>> {
+    let frames =
+        match image::guess_format(bytes) {
+            Ok(ImageFormat::Gif) => GifDecoder::new(Cursor::new(bytes))
+                .map(|decoder| within_limits(decoder.into_frames())),
+            Ok(ImageFormat::WebP) => WebPDecoder::new(Cursor::new(bytes))
+                .map(|decoder| within_limits(decoder.into_frames())),
+            _ => Ok(None),
+        };
+    match frames {
+        Ok(Some(frames)) if !frames.is_empty() => Ok(frames),
+        // Reading it as a single image gets the first frame of animations
+        _ => {
+            let mut image = ImageReader::new(Cursor::new(bytes))
+                .with_guessed_format()?
+                .decode()?;
+            let pixels = u64::from(image.width()) * u64::from(image.height());
+            if pixels > MAX_PIXELS {
+                let scale = (MAX_PIXELS as f64 / pixels as f64).sqrt();
+                image = image.resize(
+                    ((image.width() as f64 * scale) as u32).max(1),
+                    ((image.height() as f64 * scale) as u32).max(1),
+                    FilterType::Triangle,
+                );
+            }
+            Ok(vec![Frame::new(image.into_rgba8())])
+        }
+    }
+}
+
+/// Decodes the frames as long as they stay within the limits, giving up as soon as they
+/// don't so the rest aren't decoded for nothing.
+fn within_limits(frames: Frames) -> Option<Vec<Frame>> {
+    let mut decoded = Vec::new();
+    let mut pixels = 0;
+    for frame in frames {
+        let frame = frame.ok()?;
+        let (width, height) = frame.buffer().dimensions();
+        pixels += u64::from(width) * u64::from(height);
+        if decoded.len() == MAX_FRAMES || pixels > MAX_PIXELS {
+            return None;
+        }
+        decoded.push(frame);
+    }
+    Some(decoded)
+}
+
+/// The label the overlay puts in the middle of the image, transparent around the text.
+fn draw_label(width: u32, height: u32) -> Result<RgbaImage> {
+    let mut surface = Surface::new_raster_n32_premul((width as i32, height as i32))
+        .context("Couldn't make a surface for the censor label")?;
+    let canvas = surface.canvas();
+    canvas.clear(Color::TRANSPARENT);
+
+    // The four letters take about three times the size of the font
+    let size = (width as f32 / 3.5).min(height as f32 / 3.0).max(1.0);
+    let mut text_style = TextStyle::new();
+    text_style
+        .set_color(Color::WHITE)
+        .set_font_size(size)
+        .set_font_families(FONT_NAMES);
+    let mut paragraph_style = ParagraphStyle::new();
+    paragraph_style
+        .set_text_align(TextAlign::Center)
+        .set_text_style(&text_style);
+    let mut paragraph = ParagraphBuilder::new(&paragraph_style, get_font_collection())
+        .add_text(LABEL)
+        .build();
+    paragraph.layout(width as f32);
+    let top = (height as f32 - paragraph.height()) / 2.0;
+    paragraph.paint(canvas, (0.0, top));
+
+    let png = surface
+        .image_snapshot()
+        .encode_to_data(EncodedImageFormat::PNG)
+        .context("Couldn't encode the censor label")?;
+    Ok(image::load_from_memory(png.as_bytes())?.into_rgba8())
+}
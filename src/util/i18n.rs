@@ -1,55 +1,231 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use fluent_bundle::{bundle::FluentBundle, FluentResource};
+use anyhow::{anyhow, ensure, Result};
+use fluent_bundle::{bundle::FluentBundle, FluentArgs, FluentError, FluentResource, FluentValue};
 use intl_memoizer::concurrent::IntlLangMemoizer;
 use lazy_static::lazy_static;
 use phf::phf_map;
+use twilight_model::application::{
+    command::{Command, CommandOption, CommandOptionChoiceValue, CommandOptionType, CommandType},
+    interaction::Interaction,
+};
 use unic_langid::{langid, LanguageIdentifier};
 
+pub mod check;
+
 pub static AVAILABLE_LANGS: phf::Map<&'static str, &'static LanguageIdentifier> = phf_map! {
     "en-US" => &langid!("en-US"),
     "es-ES" => &langid!("es-ES"),
 };
 
+pub const DEFAULT_LANG: &str = "en-US";
+
+pub type Bundle = FluentBundle<FluentResource, IntlLangMemoizer>;
+
 lazy_static! {
-    pub static ref LANG_MAP: HashMap<LanguageIdentifier, FluentBundle<FluentResource, IntlLangMemoizer>> = {
+    pub static ref LANG_MAP: HashMap<LanguageIdentifier, Bundle> = {
         let mut map = HashMap::new();
         for (lang_name, lang_id) in AVAILABLE_LANGS.entries() {
-            let file = super::ASSETS_DIR
-                .get_file(format!("langs/{}.ftl", lang_name))
-                .expect("Couldn't find one of the lang files")
-                .contents_utf8()
-                .unwrap();
-
-            let res =
-                FluentResource::try_new(file.to_string()).expect("Failed to parse an FTL file.");
-            let mut bundle = FluentBundle::new_concurrent(vec![(*lang_id).clone()]);
-            bundle
-                .add_resource(res)
-                .expect("Failed to add FTL resources to the bundle.");
-
-            map.insert((*lang_id).clone(), bundle);
+            map.insert((*lang_id).clone(), new_bundle(lang_name, lang_id));
         }
         map
     };
+    /// en-US with every letter accented, anything that still reads as plain English
+    /// while it's enabled didn't go through the FTL files.
+    pub static ref PSEUDO_BUNDLE: Bundle = {
+        let mut bundle = new_bundle(DEFAULT_LANG, AVAILABLE_LANGS.get(DEFAULT_LANG).unwrap());
+        bundle.set_transform(Some(pseudolocalize));
+        bundle
+    };
 }
 
-pub fn get_all_of_key(key: &str) -> impl Iterator<Item = (String, Cow<str>)> + '_ {
-    LANG_MAP.iter().map(|(lang_ident, bundle)| {
-        let parts: Vec<&str> = key.split('.').collect();
-        let msg = bundle
-            .get_message(parts[0])
-            .expect("Message doesn't exist.");
-        let pattern = if let Some(attr) = parts.get(1) {
-            msg.get_attribute(attr)
-                .expect("Attribute doesn't exist.")
-                .value()
-        } else {
-            msg.value().expect("Message has no value.")
-        };
+/// Whether every text comes from [`PSEUDO_BUNDLE`], set from the config at startup.
+static PSEUDO_LOCALE: AtomicBool = AtomicBool::new(false);
 
-        let mut errors = vec![];
-        let value = bundle.format_pattern(pattern, None, &mut errors);
-        (lang_ident.to_string(), value)
-    })
+pub fn set_pseudo_locale(enabled: bool) {
+    PSEUDO_LOCALE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn get_lang_file(lang_name: &str) -> &'static str {
+    super::ASSETS_DIR
+        .get_file(format!("langs/{}.ftl", lang_name))
+        .expect("Couldn't find one of the lang files")
+        .contents_utf8()
+        .unwrap()
+}
+
+fn new_bundle(lang_name: &str, lang_id: &LanguageIdentifier) -> Bundle {
+    let res = FluentResource::try_new(get_lang_file(lang_name).to_string())
+        .expect("Failed to parse an FTL file.");
+    let mut bundle = FluentBundle::new_concurrent(vec![lang_id.clone()]);
+    // Discord doesn't need the bidi isolation marks around placeables
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(res)
+        .expect("Failed to add FTL resources to the bundle.");
+    bundle
+}
+
+fn pseudolocalize(text: &str) -> Cow<str> {
+    const FROM: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const TO: &str = "ȧƀƈḓḗƒɠħīĵķŀḿƞǿƥɋřşŧŭṽẇẋẏẑȦƁƇḒḖƑƓĦĪĴĶĿḾȠǾƤɊŘŞŦŬṼẆẊẎẐ";
+    text.chars()
+        .map(|c| FROM.find(c).map_or(c, |i| TO.chars().nth(i).unwrap()))
+        .collect::<String>()
+        .into()
+}
+
+/// Formats `message` or `message.attribute` with the bundle,
+/// the errors found while resolving it are pushed to `errors`.
+pub fn format_key(
+    bundle: &Bundle,
+    key: &str,
+    args: Option<&FluentArgs>,
+    errors: &mut Vec<FluentError>,
+) -> Result<String> {
+    let lang_ident = &bundle.locales[0];
+    let (id, attr) = match key.split_once('.') {
+        Some((id, attr)) => (id, Some(attr)),
+        None => (key, None),
+    };
+    let msg = bundle
+        .get_message(id)
+        .ok_or_else(|| anyhow!("Message {id} doesn't exist in {lang_ident}"))?;
+    let pattern = if let Some(attr) = attr {
+        msg.get_attribute(attr)
+            .ok_or_else(|| anyhow!("Attribute {key} doesn't exist in {lang_ident}"))?
+            .value()
+    } else {
+        msg.value()
+            .ok_or_else(|| anyhow!("Message {id} has no value in {lang_ident}"))?
+    };
+
+    let value = bundle.format_pattern(pattern, args, errors);
+    Ok(value.into_owned())
+}
+
+/// Formats `message` or `message.attribute` in every available language,
+/// failing if any of the bundles doesn't have it.
+pub fn get_all_of_key(key: &str) -> Result<HashMap<String, String>> {
+    LANG_MAP
+        .iter()
+        .map(|(lang_ident, bundle)| {
+            let mut errors = vec![];
+            let value = format_key(bundle, key, None, &mut errors)?;
+            ensure!(
+                errors.is_empty(),
+                "Failed to format {key} in {lang_ident}: {errors:?}"
+            );
+            Ok((lang_ident.to_string(), value))
+        })
+        .collect()
+}
+
+/// Matches a Discord locale with the closest available language,
+/// trying other regions of the same language before falling back to en-US.
+pub fn get_lang(locale: Option<&str>) -> &'static LanguageIdentifier {
+    locale
+        .and_then(|locale| {
+            AVAILABLE_LANGS.get(locale).copied().or_else(|| {
+                let language = locale.split('-').next()?;
+                AVAILABLE_LANGS
+                    .values()
+                    .find(|id| id.language.as_str() == language)
+                    .copied()
+            })
+        })
+        .unwrap_or_else(|| *AVAILABLE_LANGS.get(DEFAULT_LANG).unwrap())
+}
+
+pub fn get_interaction_lang(interaction: &Interaction) -> &'static LanguageIdentifier {
+    get_lang(interaction.locale.as_deref())
+}
+
+/// Formats a message in the given language with the arguments,
+/// falling back to en-US if it's missing from that language's bundle.
+pub fn get_text(lang: &LanguageIdentifier, key: &str, args: &[(&str, FluentValue)]) -> String {
+    let fluent_args = if args.is_empty() {
+        None
+    } else {
+        let mut fluent_args = FluentArgs::with_capacity(args.len());
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        Some(fluent_args)
+    };
+
+    let default = AVAILABLE_LANGS.get(DEFAULT_LANG).unwrap();
+    let mut bundles: Vec<&Bundle> = [lang, *default]
+        .into_iter()
+        .filter_map(|lang| LANG_MAP.get(lang))
+        .collect();
+    if PSEUDO_LOCALE.load(Ordering::Relaxed) {
+        bundles = vec![&*PSEUDO_BUNDLE];
+    }
+
+    bundles
+        .into_iter()
+        .find_map(|bundle| {
+            let mut errors = vec![];
+            match format_key(bundle, key, fluent_args.as_ref(), &mut errors) {
+                Ok(text) => {
+                    for error in errors {
+                        eprintln!("Error formatting {key} in {}: {error}", bundle.locales[0]);
+                    }
+                    Some(text)
+                }
+                Err(err) => {
+                    eprintln!("{err}");
+                    None
+                }
+            }
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Fills the name and description localizations of a command and all of its options.
+///
+/// The keys follow the layout of the FTL files: `{command}` for the command itself,
+/// `{parent}-{subcommand}` for subcommands and `{command}-options-{option}` for options,
+/// with the description in the `.desc` attribute of each one. Choices of an option are
+/// `{command}-options-{option}-{value}` and only have a name. Message commands are keyed by
+/// their name in kebab case, like `find-sauce`.
+pub fn localize_command(command: &mut Command) -> Result<()> {
+    // Message commands are named like "Find sauce", which isn't a valid key
+    let key = command.name.to_lowercase().replace(' ', "-");
+    command.name_localizations = Some(get_all_of_key(&key)?);
+    // Only slash commands have descriptions
+    if command.kind == CommandType::ChatInput {
+        command.description_localizations = Some(get_all_of_key(&format!("{key}.desc"))?);
+    }
+    localize_options(&key, &key, &mut command.options)
+}
+
+fn localize_options(command: &str, parent: &str, options: &mut [CommandOption]) -> Result<()> {
+    for option in options {
+        let key = match option.kind {
+            CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup => {
+                format!("{parent}-{}", option.name)
+            }
+            _ => format!("{command}-options-{}", option.name),
+        };
+        option.name_localizations = Some(get_all_of_key(&key)?);
+        option.description_localizations = Some(get_all_of_key(&format!("{key}.desc"))?);
+        for choice in option.choices.iter_mut().flatten() {
+            let value = match &choice.value {
+                CommandOptionChoiceValue::String(value) => value.clone(),
+                CommandOptionChoiceValue::Integer(value) => value.to_string(),
+                CommandOptionChoiceValue::Number(value) => value.to_string(),
+            };
+            choice.name_localizations = Some(get_all_of_key(&format!("{key}-{value}"))?);
+        }
+        if let Some(options) = &mut option.options {
+            localize_options(command, &key, options)?;
+        }
+    }
+    Ok(())
 }
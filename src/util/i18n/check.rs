@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use fluent_bundle::{FluentArgs, FluentResource};
+use fluent_syntax::ast::{
+    CallArguments, Entry, Expression, InlineExpression, Pattern, PatternElement,
+};
+
+use super::{format_key, get_lang_file, AVAILABLE_LANGS, DEFAULT_LANG, LANG_MAP};
+
+/// What a message defines, used to compare it between languages.
+#[derive(Debug, Default)]
+struct MessageShape {
+    has_value: bool,
+    attributes: BTreeSet<String>,
+    variables: BTreeSet<String>,
+}
+
+/// Compares every FTL file against the en-US one and tries to format all of their messages,
+/// returning a line for each problem found.
+pub fn check_langs() -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut shapes = BTreeMap::new();
+    for lang_name in AVAILABLE_LANGS.keys() {
+        let resource = match FluentResource::try_new(get_lang_file(lang_name).to_string()) {
+            Ok(resource) => resource,
+            Err((resource, errors)) => {
+                for error in errors {
+                    problems.push(format!(
+                        "{lang_name}: parse error at {:?}: {error}",
+                        error.pos
+                    ));
+                }
+                resource
+            }
+        };
+        shapes.insert(*lang_name, collect_shapes(&resource));
+    }
+
+    let default = &shapes[DEFAULT_LANG];
+    for (lang_name, messages) in shapes.iter().filter(|(x, _)| **x != DEFAULT_LANG) {
+        for id in default
+            .keys()
+            .filter(|x| !messages.contains_key(x.as_str()))
+        {
+            problems.push(format!("{lang_name}: missing message {id}"));
+        }
+        for id in messages
+            .keys()
+            .filter(|x| !default.contains_key(x.as_str()))
+        {
+            problems.push(format!("{lang_name}: message {id} isn't in {DEFAULT_LANG}"));
+        }
+
+        for (id, shape) in messages {
+            let expected = if let Some(expected) = default.get(id) {
+                expected
+            } else {
+                continue;
+            };
+            if shape.has_value != expected.has_value {
+                problems.push(format!(
+                    "{lang_name}: {id} {} a value unlike {DEFAULT_LANG}",
+                    if shape.has_value { "has" } else { "lacks" }
+                ));
+            }
+            for attr in expected.attributes.difference(&shape.attributes) {
+                problems.push(format!("{lang_name}: missing attribute {id}.{attr}"));
+            }
+            for attr in shape.attributes.difference(&expected.attributes) {
+                problems.push(format!(
+                    "{lang_name}: attribute {id}.{attr} isn't in {DEFAULT_LANG}"
+                ));
+            }
+            for var in expected.variables.difference(&shape.variables) {
+                problems.push(format!("{lang_name}: {id} doesn't use ${var}"));
+            }
+            for var in shape.variables.difference(&expected.variables) {
+                problems.push(format!(
+                    "{lang_name}: {id} uses ${var} which {DEFAULT_LANG} doesn't pass"
+                ));
+            }
+        }
+    }
+
+    // Format everything with dummy arguments to catch what format_pattern reports
+    for (lang_name, lang_id) in AVAILABLE_LANGS.entries() {
+        let bundle = &LANG_MAP[*lang_id];
+        for (id, shape) in &shapes[lang_name] {
+            let mut args = FluentArgs::new();
+            for var in &shape.variables {
+                args.set(var.as_str(), var.as_str());
+            }
+
+            let keys = shape
+                .has_value
+                .then(|| id.clone())
+                .into_iter()
+                .chain(shape.attributes.iter().map(|attr| format!("{id}.{attr}")));
+            for key in keys {
+                let mut errors = vec![];
+                if let Err(err) = format_key(bundle, &key, Some(&args), &mut errors) {
+                    problems.push(format!("{lang_name}: {err}"));
+                }
+                for error in errors {
+                    problems.push(format!("{lang_name}: error formatting {key}: {error}"));
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+fn collect_shapes(resource: &FluentResource) -> BTreeMap<String, MessageShape> {
+    let mut map = BTreeMap::new();
+    for entry in resource.entries() {
+        if let Entry::Message(message) = entry {
+            let mut shape = MessageShape {
+                has_value: message.value.is_some(),
+                ..Default::default()
+            };
+            if let Some(value) = &message.value {
+                collect_pattern(value, &mut shape.variables);
+            }
+            for attr in &message.attributes {
+                shape.attributes.insert(attr.id.name.to_string());
+                collect_pattern(&attr.value, &mut shape.variables);
+            }
+            map.insert(message.id.name.to_string(), shape);
+        }
+    }
+    map
+}
+
+fn collect_pattern(pattern: &Pattern<&str>, variables: &mut BTreeSet<String>) {
+    for element in &pattern.elements {
+        if let PatternElement::Placeable { expression } = element {
+            collect_expression(expression, variables);
+        }
+    }
+}
+
+fn collect_expression(expression: &Expression<&str>, variables: &mut BTreeSet<String>) {
+    match expression {
+        Expression::Select { selector, variants } => {
+            collect_inline(selector, variables);
+            for variant in variants {
+                collect_pattern(&variant.value, variables);
+            }
+        }
+        Expression::Inline(inline) => collect_inline(inline, variables),
+    }
+}
+
+fn collect_inline(inline: &InlineExpression<&str>, variables: &mut BTreeSet<String>) {
+    match inline {
+        InlineExpression::VariableReference { id } => {
+            variables.insert(id.name.to_string());
+        }
+        InlineExpression::FunctionReference { arguments, .. }
+        | InlineExpression::TermReference {
+            arguments: Some(arguments),
+            ..
+        } => collect_arguments(arguments, variables),
+        InlineExpression::Placeable { expression } => collect_expression(expression, variables),
+        _ => {}
+    }
+}
+
+fn collect_arguments(arguments: &CallArguments<&str>, variables: &mut BTreeSet<String>) {
+    for positional in &arguments.positional {
+        collect_inline(positional, variables);
+    }
+    for named in &arguments.named {
+        collect_inline(&named.value, variables);
+    }
+}
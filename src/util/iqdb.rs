@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use reqwest::multipart;
+use scraper::{ElementRef, Html, Selector};
+use sentry::{add_breadcrumb, Breadcrumb, Level};
+use twilight_model::http::attachment::Attachment as HttpAttachment;
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    cache::SauceCache,
+    censor::Censor,
+    i18n::get_text,
+    web::{Service, WebClient, MAX_DOWNLOAD_SIZE},
+    SauceImage,
+};
+
+/// Searches in IQDB unless the image is cached, IQDB has no API so its page gets scraped.
+pub async fn fetch(
+    web: &WebClient,
+    cache: &SauceCache,
+    image: &SauceImage,
+) -> Result<IqdbResponse> {
+    add_breadcrumb(Breadcrumb {
+        category: Some("iqdb".into()),
+        message: Some(format!("Searching in IQDB about {} ", image.url)),
+        level: Level::Info,
+        ..Default::default()
+    });
+
+    let bytes = web.get_bytes_limited(&image.url, MAX_DOWNLOAD_SIZE).await?;
+    let hashes = cache.hashes(&bytes).await?;
+    let base = web.url(Service::Iqdb, "")?;
+    if let Some(html) = cache.get("iqdb", "", hashes.as_ref()).await? {
+        let matches = parse(&html, &base).context("The cached IQDB page has no results")?;
+        return Ok(IqdbResponse {
+            matches,
+            cached: true,
+        });
+    }
+
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(bytes.to_vec())
+            .file_name(image.file_name().to_string())
+            .mime_str(image.content_type.as_deref().unwrap_or("image/jpeg"))?,
+    );
+    let request = web.post(web.url(Service::Iqdb, "/")?).multipart(form);
+    let html = web.send(request).await?.error_for_status()?.text().await?;
+    // It answers with a page without results when it can't read the image
+    let matches = parse(&html, &base).context("IQDB couldn't search the image")?;
+    if let Err(err) = cache.set("iqdb", "", hashes.as_ref(), &html).await {
+        eprintln!("Couldn't cache the IQDB search: {err}");
+    }
+    Ok(IqdbResponse {
+        matches,
+        cached: false,
+    })
+}
+
+#[derive(Debug)]
+pub struct IqdbResponse {
+    pub matches: Vec<IqdbMatch>,
+    /// Whether it came from the [`SauceCache`] instead of IQDB
+    pub cached: bool,
+}
+
+/// A table of IQDB's results page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IqdbMatch {
+    pub url: String,
+    pub thumbnail: String,
+    /// Name of the booru it's from
+    pub site: String,
+    /// Like `500×700`
+    pub size: Option<String>,
+    pub rating: Rating,
+    pub similarity: f64,
+}
+
+/// How explicit the boorus say the image is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rating {
+    Safe,
+    Questionable,
+    Explicit,
+    Unknown,
+}
+
+impl Rating {
+    /// Anything that isn't known to be safe gets censored outside NSFW channels.
+    pub fn is_nsfw(self) -> bool {
+        self != Rating::Safe
+    }
+}
+
+/// The best and additional matches in the page, the first table is the uploaded image and
+/// the possible matches are usually unrelated. `base` completes the thumbnail paths.
+///
+/// `None` if it isn't a page of results.
+pub fn parse(html: &str, base: &str) -> Option<Vec<IqdbMatch>> {
+    let document = Html::parse_document(html);
+    let pages_selector = Selector::parse("#pages").unwrap();
+    let table_selector = Selector::parse("#pages > div > table").unwrap();
+    let heading_selector = Selector::parse("th").unwrap();
+    let link_selector = Selector::parse("td.image a").unwrap();
+    let image_selector = Selector::parse("td.image img").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+
+    let pages = document.select(&pages_selector).next()?;
+    let mut matches = Vec::new();
+    for table in pages.select(&table_selector) {
+        let heading = table
+            .select(&heading_selector)
+            .next()
+            .map(text)
+            .unwrap_or_default();
+        if heading != "Best match" && heading != "Additional match" {
+            continue;
+        }
+        let (Some(link), Some(image)) = (
+            table
+                .select(&link_selector)
+                .next()
+                .and_then(|x| x.value().attr("href")),
+            table
+                .select(&image_selector)
+                .next()
+                .and_then(|x| x.value().attr("src")),
+        ) else {
+            continue;
+        };
+        // The cells after the image are the site, size with rating and similarity
+        let cells: Vec<ElementRef> = table.select(&cell_selector).skip(1).collect();
+        let (size, rating) = cells
+            .get(1)
+            .map(|x| parse_size(&text(*x)))
+            .unwrap_or((None, Rating::Unknown));
+        matches.push(IqdbMatch {
+            url: absolute_url(link, base),
+            thumbnail: absolute_url(image, base),
+            site: cells.first().map(|x| own_text(*x)).unwrap_or_default(),
+            size,
+            rating,
+            similarity: cells
+                .iter()
+                .find_map(|x| text(*x).strip_suffix("% similarity")?.parse().ok())
+                .unwrap_or(0.0),
+        });
+    }
+    Some(matches)
+}
+
+fn text(element: ElementRef) -> String {
+    element.text().collect::<String>().trim().to_string()
+}
+
+/// Leaves out the text of its children, like the other boorus next to the site's name.
+fn own_text(element: ElementRef) -> String {
+    element
+        .children()
+        .filter_map(|x| x.value().as_text().map(|x| &**x))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// IQDB links are protocol relative and its thumbnails relative to itself.
+fn absolute_url(url: &str, base: &str) -> String {
+    if let Some(url) = url.strip_prefix("//") {
+        format!("https://{url}")
+    } else if url.starts_with('/') {
+        format!("{}{url}", base.trim_end_matches('/'))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Splits rows like `500×700 [Safe]`.
+fn parse_size(row: &str) -> (Option<String>, Rating) {
+    let (size, rating) = match row.split_once('[') {
+        Some((size, rating)) => (size.trim(), rating.trim_end_matches(']')),
+        None => (row.trim(), ""),
+    };
+    let rating = match rating {
+        "Safe" => Rating::Safe,
+        "Ero" | "Questionable" => Rating::Questionable,
+        "Explicit" => Rating::Explicit,
+        _ => Rating::Unknown,
+    };
+    ((!size.is_empty()).then(|| size.to_string()), rating)
+}
+
+/// Laid out like [`super::saucenao::build_embed`], the site goes in the footer.
+pub async fn build_embed(
+    web: &WebClient,
+    data: &IqdbMatch,
+    nsfw_channel: bool,
+    censor: Censor,
+    cached: bool,
+    lang: &LanguageIdentifier,
+) -> Result<(EmbedBuilder, Option<HttpAttachment>)> {
+    let nsfw = data.rating.is_nsfw() && !nsfw_channel;
+    let image = censor.attach(web, &data.thumbnail, "iqdb", nsfw).await?;
+    let similarity = get_text(
+        lang,
+        "sauce-iqdb-similarity",
+        &[("amount", data.similarity.to_string().into())],
+    );
+    let footer = [
+        Some(data.site.clone()),
+        data.size.clone(),
+        cached.then(|| get_text(lang, "sauce-cached", &[])),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+    let mut embed = EmbedBuilder::new()
+        .title(&data.url)
+        .url(&data.url)
+        .description(match image.warning {
+            Some(warning) => format!("{similarity}\n{}", get_text(lang, warning, &[])),
+            None => similarity,
+        })
+        .footer(EmbedFooterBuilder::new(footer))
+        .color(0x2f5fa7);
+    if let Some(source) = image.source()? {
+        embed = embed.image(source);
+    }
+    Ok((embed, image.attachment))
+}
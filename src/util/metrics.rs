@@ -0,0 +1,49 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use hyper::{
+    header::CONTENT_TYPE,
+    server::conn::AddrIncoming,
+    service::{make_service_fn, service_fn},
+    Body, Response, Server,
+};
+
+use crate::config::MetricsConfig;
+
+/// Serves what `render` returns at every path in the Prometheus text format, so anything
+/// can scrape it.
+///
+/// Returns the address it's listening on, like [`super::mock::start`].
+pub fn start(
+    config: &MetricsConfig,
+    render: impl Fn() -> String + Send + Sync + 'static,
+) -> Result<SocketAddr> {
+    let incoming = AddrIncoming::bind(&config.addr)?;
+    let addr = incoming.local_addr();
+    println!("Serving metrics at {addr}");
+
+    let render = Arc::new(render);
+    let make_service = make_service_fn(move |_| {
+        let render = render.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_req| {
+                let body = render();
+                async move {
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                }
+            }))
+        }
+    });
+    let server = Server::builder(incoming).serve(make_service);
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            eprintln!("Metrics server error: {:?}", err);
+        }
+    });
+    Ok(addr)
+}
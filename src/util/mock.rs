@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, convert::Infallible, fs, net::SocketAddr, path::Path, sync::Arc};
+
+use anyhow::{Context, Result};
+use hyper::{
+    server::conn::AddrIncoming,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::MockConfig;
+
+/// A recorded response, the mock server answers with the first one matching the request.
+#[derive(Debug, Deserialize)]
+pub struct Fixture {
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Path including the service prefix, like `/saucenao/search.php`
+    pub path: String,
+    /// Query parameters the request has to include, the rest are ignored
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default = "default_status")]
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Sent as is if it's a string, otherwise as JSON
+    pub body: Option<Value>,
+    /// File relative to the fixtures folder to send instead of `body`, for images and such
+    pub body_file: Option<String>,
+    #[serde(skip)]
+    bytes: Vec<u8>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+impl Fixture {
+    fn matches(&self, req: &Request<Body>) -> bool {
+        if !req.method().as_str().eq_ignore_ascii_case(&self.method)
+            || req.uri().path() != self.path
+        {
+            return false;
+        }
+        let query: BTreeMap<_, _> = Url::parse(&format!("http://mock{}", req.uri()))
+            .map(|x| x.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+        self.query.iter().all(|(k, v)| query.get(k) == Some(v))
+    }
+
+    fn response(&self) -> Response<Body> {
+        let mut builder = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(self.bytes.clone().into()).unwrap()
+    }
+}
+
+/// Reads every `.json` file in the folder, each one being a list of fixtures.
+/// `{{mock}}` in them gets replaced with `base_url` so responses can link back to the server.
+pub fn load_fixtures(dir: &Path, base_url: &str) -> Result<Vec<Fixture>> {
+    let mut fixtures = Vec::new();
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Couldn't read the fixtures in {}", dir.display()))?
+        .map(|x| x.map(|x| x.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    for path in paths
+        .iter()
+        .filter(|x| x.extension() == Some("json".as_ref()))
+    {
+        let text = fs::read_to_string(path)?.replace("{{mock}}", base_url);
+        let file: Vec<Fixture> = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid fixture file {}", path.display()))?;
+        for mut fixture in file {
+            fixture.bytes = match (&fixture.body_file, &fixture.body) {
+                (Some(file), _) => fs::read(dir.join(file))
+                    .with_context(|| format!("Couldn't read the fixture body {file}"))?,
+                (None, Some(Value::String(text))) => text.clone().into_bytes(),
+                (None, Some(json)) => serde_json::to_vec(json)?,
+                (None, None) => Vec::new(),
+            };
+            fixtures.push(fixture);
+        }
+    }
+    Ok(fixtures)
+}
+
+/// Serves the fixtures in the background so every service can run offline, their base URLs
+/// point to `http://{addr}/{service}` while it's enabled.
+///
+/// Returns the address it's listening on, which is only different from the configured one
+/// when asking for port 0.
+pub fn start(config: &MockConfig) -> Result<SocketAddr> {
+    let incoming = AddrIncoming::bind(&config.addr)?;
+    let addr = incoming.local_addr();
+    let fixtures = Arc::new(load_fixtures(&config.fixtures, &format!("http://{addr}"))?);
+    println!("Serving {} mock fixtures at {addr}", fixtures.len());
+
+    let make_service = make_service_fn(move |_| {
+        let fixtures = fixtures.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let fixtures = fixtures.clone();
+                async move { Ok::<_, Infallible>(respond(&fixtures, &req)) }
+            }))
+        }
+    });
+    let server = Server::builder(incoming).serve(make_service);
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            eprintln!("Mock server error: {:?}", err);
+        }
+    });
+    Ok(addr)
+}
+
+fn respond(fixtures: &[Fixture], req: &Request<Body>) -> Response<Body> {
+    if let Some(fixture) = fixtures.iter().find(|x| x.matches(req)) {
+        fixture.response()
+    } else {
+        eprintln!("No mock fixture for {} {}", req.method(), req.uri());
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(format!("No fixture for {} {}", req.method(), req.uri()).into())
+            .unwrap()
+    }
+}
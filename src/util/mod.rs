@@ -1,16 +1,14 @@
 use std::{
     fmt::{Display, Formatter},
-    io::Cursor,
     sync::Arc,
     time::Duration,
 };
 
 use anyhow::{ensure, Result};
-use bytes::Bytes;
 use futures::{stream::TryStreamExt, StreamExt};
-use image::{imageops::FilterType, io::Reader as ImageReader};
 use include_dir::{include_dir, Dir};
 use lazy_static::lazy_static;
+use rand::{seq::SliceRandom, thread_rng};
 
 use regex::Regex;
 use skia_safe::{
@@ -19,13 +17,17 @@ use skia_safe::{
     Data, Typeface,
 };
 use substring::Substring;
-use tokio::{task, time::timeout};
+use tokio::{select, time::timeout};
+use tokio_util::sync::CancellationToken;
 use twilight_http::Client as HttpClient;
 use twilight_model::{
     application::interaction::{Interaction, InteractionData},
-    channel::message::{
-        component::{ActionRow, Button, ButtonStyle, Component},
-        embed::Embed,
+    channel::{
+        message::{
+            component::{ActionRow, Button, ButtonStyle, Component},
+            embed::Embed,
+        },
+        Attachment as ChannelAttachment, Message,
     },
     http::{
         attachment::Attachment,
@@ -36,12 +38,26 @@ use twilight_model::{
 };
 use twilight_standby::Standby;
 use twilight_util::builder::InteractionResponseDataBuilder;
+use unic_langid::LanguageIdentifier;
 
+use self::i18n::get_text;
+
+pub mod anilist;
 pub mod animethemes;
+pub mod ascii2d;
+pub mod cache;
+pub mod censor;
 pub mod i18n;
+pub mod iqdb;
+pub mod metrics;
+pub mod mock;
+pub mod sauce;
 pub mod saucenao;
+pub mod settings;
+pub mod shards;
 pub mod stablediffusion;
 pub mod tracemoe;
+pub mod web;
 pub mod yandex;
 
 static VALID_FONTS: &[&str] = &["ttf", "ttc", "otf"];
@@ -75,6 +91,10 @@ pub static SAD_EMOJIS: &[&str] = &[
     "😖",
 ];
 
+pub fn random_sad_emoji() -> &'static str {
+    SAD_EMOJIS.choose(&mut thread_rng()).unwrap()
+}
+
 pub static ASSETS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/assets");
 lazy_static! {
     pub static ref FONTS: Vec<Typeface> = {
@@ -125,29 +145,6 @@ pub fn measure_text_width<'a>(
     measure[0].width
 }
 
-pub async fn censor_image(bytes: Bytes) -> Result<Bytes> {
-    task::spawn_blocking(move || {
-        let img = ImageReader::new(Cursor::new(bytes))
-            .with_guessed_format()?
-            .decode()?;
-        let ratio = img.width() / 18;
-        let resize = img
-            .resize(18, img.height() / ratio, FilterType::Nearest)
-            .resize(img.width(), img.height(), FilterType::Nearest);
-        let mut new_bytes: Vec<u8> = Vec::new();
-        resize.write_to(
-            &mut Cursor::new(&mut new_bytes),
-            image::ImageOutputFormat::Png,
-        )?;
-        Ok(new_bytes.into())
-    })
-    .await?
-}
-
-pub async fn get_bytes(url: &str) -> Result<Bytes> {
-    Ok(reqwest::get(url).await?.bytes().await?)
-}
-
 pub fn seconds_to_timestamp(seconds: u32) -> String {
     let mut vec = Vec::new();
     let hours = seconds / 3600;
@@ -178,14 +175,156 @@ pub fn shortify(text: &str, limit: usize) -> &str {
     paragraph.substring(0, limit - 1)
 }
 
+/// An image to look for the source of, wherever it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SauceImage {
+    pub url: String,
+    /// Sent as is to the services the image gets uploaded to
+    pub content_type: Option<String>,
+    /// Whether it's a link outside Discord, which the services can download themselves
+    pub linked: bool,
+}
+
+impl SauceImage {
+    /// Guesses the content type from the extension, `None` if it doesn't look like an image
+    /// or a video.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let extension = path.rsplit_once('.')?.1.to_lowercase();
+        let content_type = match extension.as_str() {
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "bmp" => "image/bmp",
+            "mp4" => "video/mp4",
+            "webm" => "video/webm",
+            _ => return None,
+        };
+        Some(SauceImage {
+            url: url.to_string(),
+            content_type: Some(content_type.to_string()),
+            linked: false,
+        })
+    }
+
+    /// Last part of the URL's path, to tell images apart.
+    pub fn file_name(&self) -> &str {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        path.rsplit('/').next().unwrap_or(path)
+    }
+}
+
+impl From<&ChannelAttachment> for SauceImage {
+    fn from(attachment: &ChannelAttachment) -> Self {
+        SauceImage {
+            url: attachment.proxy_url.clone(),
+            content_type: attachment.content_type.clone(),
+            linked: false,
+        }
+    }
+}
+
+/// Where in a message an image was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageOrigin {
+    Attachment,
+    Embed,
+    Link,
+}
+
+impl ImageOrigin {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageOrigin::Attachment => "attachment",
+            ImageOrigin::Embed => "embed",
+            ImageOrigin::Link => "link",
+        }
+    }
+}
+
+/// Most images taken from a message, as many as a select menu fits.
+pub const MAX_MESSAGE_IMAGES: usize = 25;
+
+/// Every image in the message: the attachments, then the embed images and lastly the links
+/// to images that didn't get embedded.
+pub fn find_images(message: &Message) -> Vec<(ImageOrigin, SauceImage)> {
+    lazy_static! {
+        static ref IMAGE_LINK: Regex =
+            Regex::new(r"(?i)https?://[^\s<>]+?\.(?:png|jpe?g|gif|webp|bmp)(?:\?[^\s<>]*)?")
+                .unwrap();
+    }
+    let mut images = Vec::new();
+    for attachment in &message.attachments {
+        let is_media = attachment.content_type.as_deref().map_or_else(
+            || SauceImage::from_url(&attachment.filename).is_some(),
+            |x| x.starts_with("image/") || x.starts_with("video/"),
+        );
+        if is_media {
+            images.push((ImageOrigin::Attachment, SauceImage::from(attachment)));
+        }
+    }
+
+    // Links get embedded as thumbnails, so they're compared by the original URL
+    let mut seen = Vec::new();
+    for embed in &message.embeds {
+        let media = [
+            embed.image.as_ref().map(|x| (&x.url, &x.proxy_url)),
+            embed.thumbnail.as_ref().map(|x| (&x.url, &x.proxy_url)),
+        ];
+        for (url, proxy_url) in media.into_iter().flatten() {
+            if seen.contains(url) {
+                continue;
+            }
+            seen.push(url.clone());
+            let proxy_url = proxy_url.as_ref().unwrap_or(url);
+            images.push((
+                ImageOrigin::Embed,
+                SauceImage::from_url(url)
+                    .map(|x| SauceImage {
+                        url: proxy_url.clone(),
+                        ..x
+                    })
+                    .unwrap_or_else(|| SauceImage {
+                        url: proxy_url.clone(),
+                        content_type: None,
+                        linked: false,
+                    }),
+            ));
+        }
+    }
+    for link in IMAGE_LINK.find_iter(&message.content) {
+        if !seen.iter().any(|x| x == link.as_str()) {
+            seen.push(link.as_str().to_string());
+            if let Some(image) = SauceImage::from_url(link.as_str()) {
+                images.push((
+                    ImageOrigin::Link,
+                    SauceImage {
+                        linked: true,
+                        ..image
+                    },
+                ));
+            }
+        }
+    }
+    images.truncate(MAX_MESSAGE_IMAGES);
+    images
+}
+
 pub struct EmbedList {
     pub embeds: Vec<Embed>,
     pub attachments: Vec<Option<Attachment>>,
+    /// Sent as the message content along with the embed, to tell which group it belongs to
+    pub labels: Vec<Option<String>>,
+    /// Shown after back and next while their embed is, their command handles the clicks
+    pub buttons: Vec<Vec<Button>>,
     pub index: Arc<usize>,
     pub duration: u64,
     http: Arc<HttpClient>,
     application_id: Id<ApplicationMarker>,
     standby: Arc<Standby>,
+    lang: &'static LanguageIdentifier,
+    shutdown: CancellationToken,
 }
 
 impl EmbedList {
@@ -193,21 +332,51 @@ impl EmbedList {
         http: Arc<HttpClient>,
         application_id: Id<ApplicationMarker>,
         standby: Arc<Standby>,
+        lang: &'static LanguageIdentifier,
+        shutdown: CancellationToken,
     ) -> Self {
         EmbedList {
             embeds: Vec::new(),
             attachments: Vec::new(),
+            labels: Vec::new(),
+            buttons: Vec::new(),
             index: Arc::new(0),
             duration: 70,
             http,
             application_id,
             standby,
+            lang,
+            shutdown,
         }
     }
 
     pub fn add(&mut self, embed: Embed, attachment: Option<Attachment>) {
+        self.add_labeled(embed, attachment, None);
+    }
+
+    /// Adds an embed that's part of a group, like the results of one of many images, `label`
+    /// is shown above it.
+    pub fn add_labeled(
+        &mut self,
+        embed: Embed,
+        attachment: Option<Attachment>,
+        label: Option<String>,
+    ) {
+        self.add_with_buttons(embed, attachment, label, Vec::new());
+    }
+
+    /// Like [`EmbedList::add_labeled`] with buttons of its own, like links about it.
+    pub fn add_with_buttons(
+        &mut self,
+        embed: Embed,
+        attachment: Option<Attachment>,
+        label: Option<String>,
+        buttons: Vec<Button>,
+    ) {
         self.embeds.push(embed);
         self.attachments.push(attachment);
+        self.labels.push(label);
+        self.buttons.push(buttons);
     }
 
     pub async fn defer_reply(
@@ -224,24 +393,31 @@ impl EmbedList {
             .take(1)
             .filter_map(|x| x.to_owned())
             .collect();
-        // Just send the embed without any component
+        let own_row = self.own_row(0);
+        let client = self.http.interaction(self.application_id);
+        let mut followup = client
+            .create_followup(&interaction.token)
+            .embeds(&self.embeds[0..1])?
+            .attachments(&attachments)?;
+        if let Some(label) = &self.labels[0] {
+            followup = followup.content(label)?;
+        }
+        // Just send the embed with its own buttons
         if self.embeds.len() == 1 {
-            self.http
-                .interaction(self.application_id)
-                .create_followup(&interaction.token)
-                .embeds(&self.embeds)?
-                .attachments(&attachments)?
-                .await?;
+            if !own_row.is_empty() {
+                followup = followup.components(&own_row)?;
+            }
+            followup.await?;
             return Ok(());
         }
 
-        self.http
-            .interaction(self.application_id)
-            .create_followup(&interaction.token)
-            .embeds(&[self.embeds[0].clone()])?
-            .attachments(&attachments)?
-            .components(&[Component::ActionRow(Self::generate_row(true, false))])?
-            .await?;
+        let action_row = [Component::ActionRow(Self::generate_row(
+            self.lang,
+            true,
+            false,
+            &self.buttons[0],
+        ))];
+        followup.components(&action_row)?.await?;
 
         let message_id = self
             .http
@@ -296,8 +472,10 @@ impl EmbedList {
                             _ => panic!("unhandled custom id!"),
                         };
                         let action_row = [Component::ActionRow(Self::generate_row(
+                            list.lang,
                             index == 0,
                             index == list.embeds.len() - 1,
+                            &list.buttons[index],
                         ))];
                         let embeds = &list.embeds[index..(index + 1)];
                         //FIXME: Copying attachments just to pass them...
@@ -309,6 +487,7 @@ impl EmbedList {
                             .http
                             .interaction(list.application_id)
                             .update_response(&token)
+                            .content(list.labels[index].as_deref())?
                             .embeds(Some(embeds))?
                             .attachments(&attachments)?
                             .components(Some(&action_row))?
@@ -318,18 +497,13 @@ impl EmbedList {
                 }
             });
 
-        // Clear all components when timeout runs out
-        match timeout(Duration::from_secs(self.duration), process).await {
-            Err(_) => {
-                let _update = self
-                    .http
-                    .interaction(self.application_id)
-                    .update_response(&interaction.token)
-                    .components(Some(&[]))?
-                    .await?;
-                Ok(())
-            }
-            Ok(result) => result,
+        // Clear all components when timeout runs out or the bot is shutting down
+        select! {
+            result = timeout(Duration::from_secs(self.duration), process) => match result {
+                Err(_) => self.clear_components(interaction).await,
+                Ok(result) => result,
+            },
+            _ = self.shutdown.cancelled() => self.clear_components(interaction).await,
         }
     }
 
@@ -340,10 +514,20 @@ impl EmbedList {
     ) -> Result<()> {
         ensure!(!self.embeds.is_empty(), "There is no embeds to send!");
 
-        // Just send the embed without any component
+        let builder = match &self.labels[0] {
+            Some(label) => builder.content(label.clone()),
+            None => builder,
+        };
+        // Just send the embed with its own buttons
         if self.embeds.len() == 1 {
             let client = self.http.interaction(self.application_id);
             // FIXME: Cloning
+            let own_row = self.own_row(0);
+            let builder = if own_row.is_empty() {
+                builder
+            } else {
+                builder.components(own_row)
+            };
             let builder = builder
                 .embeds(self.embeds)
                 .attachments(self.attachments.iter().filter_map(|x| x.to_owned()));
@@ -361,7 +545,12 @@ impl EmbedList {
             .clone()
             .embeds([self.embeds[0].clone()])
             .attachments(self.attachments[0].clone())
-            .components([Component::ActionRow(Self::generate_row(true, false))]);
+            .components([Component::ActionRow(Self::generate_row(
+                self.lang,
+                true,
+                false,
+                &self.buttons[0],
+            ))]);
         let response = InteractionResponse {
             kind: InteractionResponseType::ChannelMessageWithSource,
             data: Some(first.build()),
@@ -424,8 +613,10 @@ impl EmbedList {
                             _ => panic!("unhandled custom id!"),
                         };
                         let action_row = [Component::ActionRow(Self::generate_row(
+                            list.lang,
                             index == 0,
                             index == list.embeds.len() - 1,
+                            &list.buttons[index],
                         ))];
                         let embeds = &list.embeds[index..(index + 1)];
                         //FIXME: Copying attachments just to pass them...
@@ -437,6 +628,7 @@ impl EmbedList {
                             .http
                             .interaction(list.application_id)
                             .update_response(&token)
+                            .content(list.labels[index].as_deref())?
                             .embeds(Some(embeds))?
                             .attachments(&attachments)?
                             .components(Some(&action_row))?
@@ -446,27 +638,50 @@ impl EmbedList {
                 }
             });
 
-        // Clear all components when timeout runs out
-        match timeout(Duration::from_secs(self.duration), process).await {
-            Err(_) => {
-                let _update = self
-                    .http
-                    .interaction(self.application_id)
-                    .update_response(&interaction.token)
-                    .components(Some(&[]))?
-                    .await?;
-                Ok(())
-            }
-            Ok(result) => result,
+        // Clear all components when timeout runs out or the bot is shutting down
+        select! {
+            result = timeout(Duration::from_secs(self.duration), process) => match result {
+                Err(_) => self.clear_components(interaction).await,
+                Ok(result) => result,
+            },
+            _ = self.shutdown.cancelled() => self.clear_components(interaction).await,
+        }
+    }
+
+    /// Takes out back and next, the buttons of the embed being shown keep working.
+    async fn clear_components(&self, interaction: &Interaction) -> Result<()> {
+        let action_row = self.own_row(*self.index);
+        let _update = self
+            .http
+            .interaction(self.application_id)
+            .update_response(&interaction.token)
+            .components(Some(&action_row))?
+            .await?;
+        Ok(())
+    }
+
+    /// A row with only the buttons of the embed at `index`, nothing if it has none.
+    fn own_row(&self, index: usize) -> Vec<Component> {
+        let buttons = &self.buttons[index];
+        if buttons.is_empty() {
+            return Vec::new();
         }
+        vec![Component::ActionRow(ActionRow {
+            components: buttons.iter().cloned().map(Component::Button).collect(),
+        })]
     }
 
-    fn generate_row(prev: bool, next: bool) -> ActionRow {
-        ActionRow {
+    fn generate_row(
+        lang: &LanguageIdentifier,
+        prev: bool,
+        next: bool,
+        buttons: &[Button],
+    ) -> ActionRow {
+        let mut row = ActionRow {
             components: Vec::from([
                 Component::Button(Button {
                     custom_id: Some("back".to_string()),
-                    label: Some("< Prev.".to_string()),
+                    label: Some(get_text(lang, "embedlist-back", &[])),
                     style: ButtonStyle::Secondary,
                     disabled: prev,
                     emoji: None,
@@ -474,14 +689,17 @@ impl EmbedList {
                 }),
                 Component::Button(Button {
                     custom_id: Some("next".to_string()),
-                    label: Some("Next >".to_string()),
+                    label: Some(get_text(lang, "embedlist-next", &[])),
                     style: ButtonStyle::Secondary,
                     disabled: next,
                     emoji: None,
                     url: None,
                 }),
             ]),
-        }
+        };
+        row.components
+            .extend(buttons.iter().cloned().map(Component::Button));
+        row
     }
 }
 
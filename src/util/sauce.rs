@@ -0,0 +1,228 @@
+use anyhow::{anyhow, Result};
+use twilight_model::{
+    channel::message::embed::{Embed, EmbedFooter},
+    http::attachment::Attachment as HttpAttachment,
+};
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    cache::SauceCache,
+    censor::Censor,
+    saucenao::{self, RateLimits, Res, SearchOptions, MAX_RESULTS},
+    tracemoe::{self, TraceResult},
+    web::{TooLarge, WebClient},
+    SauceImage,
+};
+
+/// The reverse image search engines `/sauce auto` asks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    SauceNao,
+    TraceMoe,
+}
+
+impl Engine {
+    /// Shown as the badge of its results, it's a brand so it isn't translated.
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::SauceNao => "SauceNAO",
+            Engine::TraceMoe => "trace.moe",
+        }
+    }
+}
+
+/// A result of any of the engines, so they can be ranked together before building any of
+/// their embeds.
+#[derive(Debug)]
+pub struct SauceMatch {
+    pub engine: Engine,
+    /// Position in the results of its engine
+    pub index: usize,
+    /// Percentage, the same for every engine
+    pub similarity: f64,
+    /// Links to where it's from, results sharing any of them are the same source
+    pub sources: Vec<String>,
+}
+
+impl SauceMatch {
+    pub fn from_saucenao(index: usize, res: &Res) -> Self {
+        let urls = res.data.get_ext_urls().unwrap_or_default();
+        SauceMatch {
+            engine: Engine::SauceNao,
+            index,
+            similarity: res.header.similarity.parse().unwrap_or(0.0),
+            sources: urls.iter().map(|x| normalize_url(x)).collect(),
+        }
+    }
+
+    pub fn from_tracemoe(index: usize, data: &TraceResult) -> Self {
+        // SauceNAO links the same AniList page in its anime results
+        let url = format!("https://anilist.co/anime/{}/", data.anilist.id);
+        SauceMatch {
+            engine: Engine::TraceMoe,
+            index,
+            similarity: data.similarity * 100.0,
+            sources: vec![normalize_url(&url)],
+        }
+    }
+
+    fn same_source(&self, other: &SauceMatch) -> bool {
+        self.sources.iter().any(|x| other.sources.contains(x))
+    }
+}
+
+/// Puts the name of the engine first in the footer.
+fn with_badge(mut embed: Embed, engine: Engine) -> Embed {
+    let footer = embed.footer.take();
+    embed.footer = Some(EmbedFooter {
+        text: match &footer {
+            Some(footer) => format!("{} · {}", engine.name(), footer.text),
+            None => engine.name().to_string(),
+        },
+        icon_url: footer.and_then(|x| x.icon_url),
+        proxy_icon_url: None,
+    });
+    embed
+}
+
+/// Leaves out what changes between links to the same page.
+fn normalize_url(url: &str) -> String {
+    let url = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.")
+        .trim_end_matches('/');
+    url.to_lowercase()
+}
+
+/// Most similar first, and only the best of the results that point to the same source.
+pub fn rank(mut matches: Vec<SauceMatch>) -> Vec<SauceMatch> {
+    matches.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    let mut ranked: Vec<SauceMatch> = Vec::new();
+    for result in matches {
+        if !ranked.iter().any(|x| x.same_source(&result)) {
+            ranked.push(result);
+        }
+    }
+    ranked.truncate(MAX_RESULTS.into());
+    ranked
+}
+
+/// What's needed to search in SauceNAO, which isn't set up in every bot.
+pub struct SauceNaoSearch<'a> {
+    pub limits: &'a RateLimits,
+    pub token: &'a str,
+    pub options: &'a SearchOptions,
+}
+
+/// Searches the image in every engine at the same time and ranks everything they find,
+/// only the results that make it get their embed (and image) built.
+/// An engine failing only leaves its results out, unless all of them fail.
+pub async fn search(
+    web: &WebClient,
+    cache: &SauceCache,
+    saucenao: Option<SauceNaoSearch<'_>>,
+    image: &SauceImage,
+    nsfw_channel: bool,
+    censor: Censor,
+    lang: &LanguageIdentifier,
+) -> Result<Vec<(Embed, Option<HttpAttachment>)>> {
+    let saucenao_search = async {
+        match &saucenao {
+            Some(search) => saucenao::fetch(
+                web,
+                search.limits,
+                cache,
+                image,
+                search.token,
+                search.options,
+            )
+            .await
+            .map(Some),
+            None => Ok(None),
+        }
+    };
+    let tracemoe_search = tracemoe::fetch(web, cache, image, &tracemoe::SearchOptions::default());
+    let (saucenao_res, tracemoe_res) = tokio::join!(saucenao_search, tracemoe_search);
+
+    let mut error = None;
+    // Out of searches or down, trace.moe can still find something
+    let saucenao_res = keep(Engine::SauceNao, saucenao_res, &mut error)?
+        .flatten()
+        .filter(|res| res.header.status == 0);
+    let tracemoe_res = match keep(Engine::TraceMoe, tracemoe_res, &mut error)? {
+        Some(res) if !res.error.is_empty() => {
+            error = Some(anyhow!(
+                "trace.moe couldn't search the image: {}",
+                res.error
+            ));
+            None
+        }
+        res => res,
+    };
+
+    let mut matches = Vec::new();
+    if let Some(res) = &saucenao_res {
+        matches.extend(
+            res.results
+                .iter()
+                .enumerate()
+                .map(|(index, data)| SauceMatch::from_saucenao(index, data)),
+        );
+    }
+    if let Some(res) = &tracemoe_res {
+        matches.extend(
+            res.result
+                .iter()
+                .take(MAX_RESULTS.into())
+                .enumerate()
+                .map(|(index, data)| SauceMatch::from_tracemoe(index, data)),
+        );
+    }
+    if matches.is_empty() {
+        if let Some(err) = error {
+            return Err(err);
+        }
+    }
+
+    let mut found = Vec::new();
+    for result in rank(matches) {
+        let (embed, attachment) = match result.engine {
+            Engine::SauceNao => {
+                let (Some(search), Some(res)) = (&saucenao, &saucenao_res) else {
+                    continue;
+                };
+                let data = &res.results[result.index];
+                saucenao::build_embed(web, data, search.options, res.cached, lang).await?
+            }
+            Engine::TraceMoe => {
+                let Some(res) = &tracemoe_res else {
+                    continue;
+                };
+                let data = &res.result[result.index];
+                tracemoe::build_embed(web, data, nsfw_channel, censor, res, false, lang).await?
+            }
+        };
+        found.push((with_badge(embed.build(), result.engine), attachment));
+    }
+    Ok(found)
+}
+
+/// The response of an engine, or nothing after putting its error aside so it's only told
+/// when every engine fails.
+fn keep<T>(
+    engine: Engine,
+    result: Result<T>,
+    error: &mut Option<anyhow::Error>,
+) -> Result<Option<T>> {
+    match result {
+        Ok(res) => Ok(Some(res)),
+        // It's the same image for every engine
+        Err(err) if err.is::<TooLarge>() => Err(err),
+        Err(err) => {
+            eprintln!("{} failed to search: {err:?}", engine.name());
+            *error = Some(err);
+            Ok(None)
+        }
+    }
+}
@@ -1,68 +1,295 @@
-use std::env;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
+use anyhow::ensure;
 use chrono::prelude::*;
 use sentry::{add_breadcrumb, Breadcrumb, Level};
-use serde::de::{Deserializer, Error};
+use serde::de::{DeserializeOwned, Deserializer, Error};
 use serde::Deserialize;
 use serde_json::Value;
+use tokio::time::sleep;
+use twilight_interactions::command::{CommandOption, CreateOption};
 use twilight_model::channel::message::embed::EmbedFooter;
+use twilight_model::http::attachment::Attachment as HttpAttachment;
 use twilight_model::util::Timestamp;
-use twilight_model::{channel::Attachment, http::attachment::Attachment as HttpAttachment};
 use twilight_util::builder::embed::{
     EmbedAuthorBuilder, EmbedBuilder, EmbedFieldBuilder, ImageSource,
 };
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    cache::SauceCache,
+    censor::{Censor, NsfwPolicy},
+    i18n::get_text,
+    shortify,
+    web::{Service, WebClient, MAX_DOWNLOAD_SIZE},
+    SauceImage,
+};
 
-use super::{censor_image, get_bytes, shortify};
+/// Most results SauceNAO gives back, and as many pages as an EmbedList should have.
+pub const MAX_RESULTS: u8 = 10;
+
+/// Groups of SauceNAO indexes that can be searched on their own.
+#[derive(CommandOption, CreateOption, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Database {
+    #[option(name = "All sites", value = "all")]
+    All,
+    #[option(name = "Anime, movies and shows", value = "anime")]
+    Anime,
+    #[option(name = "Boorus", value = "booru")]
+    Booru,
+    #[option(name = "Pixiv", value = "pixiv")]
+    Pixiv,
+    #[option(name = "Manga and doujinshi", value = "manga")]
+    Manga,
+    #[option(name = "Art sites and social media", value = "art")]
+    Art,
+}
+
+impl Database {
+    /// Ids of the indexes in the group, `None` meaning every index.
+    pub fn indexes(self) -> Option<&'static [u32]> {
+        match self {
+            Database::All => None,
+            Database::Anime => Some(&[21, 22, 23, 24]),
+            Database::Booru => Some(&[9, 12, 25, 26, 27, 28, 29, 30]),
+            Database::Pixiv => Some(&[5, 6]),
+            Database::Manga => Some(&[0, 16, 18, 36, 37, 38]),
+            Database::Art => Some(&[8, 10, 11, 19, 20, 31, 32, 34, 35, 39, 40, 41, 42, 43, 44]),
+        }
+    }
 
-pub async fn fetch(attachment: &Attachment) -> anyhow::Result<Data> {
-    let client = reqwest::Client::new();
+    /// Same as the value of its choice, which is also the end of its FTL key.
+    pub fn name(self) -> &'static str {
+        match self {
+            Database::All => "all",
+            Database::Anime => "anime",
+            Database::Booru => "booru",
+            Database::Pixiv => "pixiv",
+            Database::Manga => "manga",
+            Database::Art => "art",
+        }
+    }
+}
+
+/// How a search is done and which of its results are kept.
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub database: Database,
+    /// Between 1 and [`MAX_RESULTS`]
+    pub results: u8,
+    /// Percentage, the results less similar than it are dropped
+    pub min_similarity: f64,
+    /// NSFW channels get every result uncensored, the rest only the ones SauceNAO thinks are safe
+    pub nsfw_channel: bool,
+    /// What happens to the results that aren't safe outside NSFW channels
+    pub censor: Censor,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            database: Database::All,
+            results: 5,
+            min_similarity: 0.0,
+            nsfw_channel: false,
+            censor: Censor::default(),
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Checks the options of the command, Discord enforces the same limits already.
+    pub fn new(
+        database: Option<Database>,
+        results: Option<i64>,
+        min_similarity: Option<f64>,
+        nsfw_channel: bool,
+        censor: Censor,
+    ) -> anyhow::Result<Self> {
+        let default = Self::default();
+        let results = results.unwrap_or(default.results.into());
+        ensure!(
+            (1..=MAX_RESULTS.into()).contains(&results),
+            "Can't show {results} results"
+        );
+        let min_similarity = min_similarity.unwrap_or(default.min_similarity);
+        ensure!(
+            (0.0..=100.0).contains(&min_similarity),
+            "{min_similarity}% isn't a valid similarity"
+        );
+        Ok(SearchOptions {
+            database: database.unwrap_or(default.database),
+            results: results as u8,
+            min_similarity,
+            nsfw_channel,
+            censor,
+        })
+    }
+
+    /// SauceNAO's `hide` parameter, 0 shows everything and 3 only what's expected to be safe.
+    /// The rest of the policies need the NSFW results to censor them or put them behind a
+    /// spoiler, so they're only left out when the guild wants them hidden.
+    pub fn hide(&self) -> u8 {
+        if !self.nsfw_channel && self.censor.policy == NsfwPolicy::Hide {
+            3
+        } else {
+            0
+        }
+    }
+
+    /// The filters that aren't the default ones, to show them next to the results.
+    pub fn describe(&self, lang: &LanguageIdentifier) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.database != Database::All {
+            parts.push(get_text(
+                lang,
+                &format!("sauce-options-database-{}", self.database.name()),
+                &[],
+            ));
+        }
+        if self.min_similarity > 0.0 {
+            parts.push(get_text(
+                lang,
+                "sauce-saucenao-min-similarity",
+                &[("amount", self.min_similarity.to_string().into())],
+            ));
+        }
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+}
+
+/// Searches in SauceNAO unless the image is cached. Once the searches run out it waits up to
+/// [`MAX_QUEUE_WAIT`] for the next one, or answers with [`RATE_LIMITED_STATUS`] without asking.
+pub async fn fetch(
+    web: &WebClient,
+    limits: &RateLimits,
+    cache: &SauceCache,
+    image: &SauceImage,
+    token: &str,
+    options: &SearchOptions,
+) -> anyhow::Result<Data> {
     add_breadcrumb(Breadcrumb {
         category: Some("saucenao".into()),
-        message: Some(format!(
-            "Searching in saucenao about {} ",
-            attachment.proxy_url.clone()
-        )),
+        message: Some(format!("Searching in saucenao about {} ", image.url)),
         level: Level::Info,
         ..Default::default()
     });
-    let res = client
-        .get("https://saucenao.com/search.php?db=999&output_type=2&numres=5&hide=3")
-        .query(&[
-            ("api_key", env::var("SAUCENAO_TOKEN")?),
-            ("url", attachment.proxy_url.clone()),
-        ])
-        .send()
-        .await?
-        .json::<Data>()
-        .await?;
+    // SauceNAO gets the URL, the image is only downloaded to look it up in the cache
+    let hashes = if cache.enabled() {
+        let bytes = web.get_bytes_limited(&image.url, MAX_DOWNLOAD_SIZE).await?;
+        cache.hashes(&bytes).await?
+    } else {
+        None
+    };
+    let mut query = vec![
+        ("output_type", "2".to_string()),
+        ("numres", options.results.to_string()),
+        ("hide", options.hide().to_string()),
+    ];
+    if let Some(indexes) = options.database.indexes() {
+        let mask = indexes.iter().fold(0u64, |mask, index| mask | 1 << index);
+        query.push(("dbmask", mask.to_string()));
+    } else {
+        query.push(("db", "999".to_string()));
+    }
+    let params = query
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut res = if let Some(text) = cache.get("saucenao", &params, hashes.as_ref()).await? {
+        let mut res = serde_json::from_str::<Data>(&text)?;
+        res.cached = true;
+        res
+    } else {
+        if limits.take(MAX_QUEUE_WAIT).await.is_err() {
+            return Ok(Data {
+                header: DataHeader {
+                    status: RATE_LIMITED_STATUS,
+                    ..Default::default()
+                },
+                results: Vec::new(),
+                cached: false,
+            });
+        }
+        query.push(("api_key", token.to_string()));
+        query.push(("url", image.url.clone()));
+        // Its 429s come with how many searches are left, which the limits have to see first
+        let text = web
+            .send_rate_limited(
+                web.get(web.url(Service::SauceNao, "/search.php")?)
+                    .query(&query),
+            )
+            .await?
+            .text()
+            .await?;
+        let res = serde_json::from_str::<Data>(&text)?;
+        limits.update(&res.header);
+        if res.header.status == 0 {
+            if let Err(err) = cache.set("saucenao", &params, hashes.as_ref(), &text).await {
+                eprintln!("Couldn't cache the SauceNAO search: {err}");
+            }
+        }
+        res
+    };
+    res.results.retain(|x| {
+        x.header
+            .similarity
+            .parse::<f64>()
+            .map_or(true, |similarity| similarity >= options.min_similarity)
+    });
+    res.results.truncate(options.results.into());
     Ok(res)
 }
 
-const NSFW_WARN: &str = "\n**WARNING:** Image is NSFW so it's been censored!";
+/// `cached` marks the footer so it's known the result could be outdated.
 pub async fn build_embed(
+    web: &WebClient,
     res: &Res,
-    nsfw_channel: bool,
-) -> anyhow::Result<(EmbedBuilder, HttpAttachment)> {
-    let nsfw = res.header.hidden != 0 && !nsfw_channel;
-    let image = if nsfw {
-        censor_image(get_bytes(&res.header.thumbnail).await?).await?
-    } else {
-        get_bytes(&res.header.thumbnail).await?
-    };
-    let attachment = HttpAttachment::from_bytes("saucenao.png".to_string(), image.into(), 1);
+    options: &SearchOptions,
+    cached: bool,
+    lang: &LanguageIdentifier,
+) -> anyhow::Result<(EmbedBuilder, Option<HttpAttachment>)> {
+    let nsfw_site = options.censor.nsfw_sites && res.data.is_nsfw_site();
+    let nsfw = (res.header.hidden != 0 || nsfw_site) && !options.nsfw_channel;
+    let image = options
+        .censor
+        .attach(web, &res.header.thumbnail, "saucenao", nsfw)
+        .await?;
+    let similarity = get_text(
+        lang,
+        "sauce-saucenao-similarity",
+        &[("amount", res.header.similarity.as_str().into())],
+    );
     let mut embed = EmbedBuilder::new()
-        .description(format!(
-            "Similarity {}%{}",
-            res.header.similarity,
-            if nsfw { NSFW_WARN } else { "" }
-        ))
-        .image(ImageSource::attachment("saucenao.png")?)
+        .description(match image.warning {
+            Some(warning) => format!("{similarity}\n{}", get_text(lang, warning, &[])),
+            None => similarity,
+        })
         .footer(EmbedFooter {
             icon_url: None,
             proxy_icon_url: None,
-            text: res.header.index_name.clone(),
+            text: [
+                Some(res.header.index_name.clone()),
+                options.describe(lang),
+                cached.then(|| get_text(lang, "sauce-cached", &[])),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" · "),
         })
         .color(0x9b59b6);
+    if let Some(source) = image.source()? {
+        embed = embed.image(source);
+    }
 
     {
         let ext_urls = res.data.get_ext_urls();
@@ -80,11 +307,11 @@ pub async fn build_embed(
                 .title(data.title),
             ResData::Anime(SauceAnimeData { part, est_time, source, .. })
             | ResData::Shows(SauceShowData { part, est_time, source, ..}) => if let Some(part) = part {
-                embed.field(EmbedFieldBuilder::new("Part:", part).inline())
+                embed.field(part_field(lang, &part).inline())
             } else {
                 embed
             }
-            .field(EmbedFieldBuilder::new("Timestamp:", est_time).inline())
+            .field(timestamp_field(lang, &est_time).inline())
             .title(source),
             ResData::DeviantArt(SauceDeviantArtData {
                 title,
@@ -113,7 +340,11 @@ pub async fn build_embed(
                 )),
             ),
             ResData::Twitter(twitter) => embed
-                .title(format!("Tweet by {}", twitter.twitter_user_handle))
+                .title(get_text(
+                    lang,
+                    "sauce-saucenao-tweet",
+                    &[("user-handle", twitter.twitter_user_handle.into())],
+                ))
                 .timestamp(Timestamp::from_secs(
                     twitter.created_at.parse::<DateTime<Utc>>()?.timestamp(),
                 )?),
@@ -130,7 +361,11 @@ pub async fn build_embed(
                 EmbedAuthorBuilder::new(member_name)
                     .url(format!("https://bcy.net/u/{}", member_link_id)),
             ),
-            ResData::Pawoo(pawoo) => embed.title(format!("Toot by {}", pawoo.pawoo_user_username)),
+            ResData::Pawoo(pawoo) => embed.title(get_text(
+                lang,
+                "sauce-saucenao-toot",
+                &[("pawoo-user", pawoo.pawoo_user_username.into())],
+            )),
             ResData::FAKKU(SauceFAKKUData {
                 source, creator, ..
             })
@@ -154,7 +389,7 @@ pub async fn build_embed(
             }) => embed.title(source).author(EmbedAuthorBuilder::new(creator)),
             ResData::Madokami(madokami) => embed
                 .title(madokami.source)
-                .field(EmbedFieldBuilder::new("Part:", madokami.part).inline()),
+                .field(part_field(lang, &madokami.part).inline()),
             ResData::Kemono(kemono) => embed.title(kemono.title).author(
                 EmbedAuthorBuilder::new(kemono.user_name)
                     .url(kemono.ext_urls[1].clone())
@@ -165,31 +400,55 @@ pub async fn build_embed(
             ),
             ResData::Mangadex(mangadex) => embed
                 .title(mangadex.source)
-                .author(EmbedAuthorBuilder::new(format!(
-                    "{}{}",
-                    mangadex.author,
-                    if mangadex.author.contains(&mangadex.artist) {
-                        format!(", {}", mangadex.artist)
-                    } else {
-                        "".to_string()
-                    }
+                .author(EmbedAuthorBuilder::new(get_text(
+                    lang,
+                    "sauce-saucenao-mangadex-authors",
+                    &[
+                        ("author", mangadex.author.as_str().into()),
+                        (
+                            "artist",
+                            if !mangadex.author.contains(&mangadex.artist) {
+                                mangadex.artist.as_str()
+                            } else {
+                                "none"
+                            }
+                            .into(),
+                        ),
+                    ],
                 )))
-                .field(EmbedFieldBuilder::new("Chapter:", mangadex.part).inline()),
+                .field(
+                    EmbedFieldBuilder::new(
+                        get_text(lang, "sauce-saucenao-mangadex-part", &[]),
+                        get_text(
+                            lang,
+                            "sauce-saucenao-mangadex-part.value",
+                            &[("type", "chapter".into()), ("num", mangadex.part.into())],
+                        ),
+                    )
+                    .inline(),
+                ),
             ResData::Artstation(artstation) => embed
                 .title(artstation.title)
                 .author(EmbedAuthorBuilder::new(artstation.author_name).url(artstation.author_url)),
             ResData::Skeb(skeb) => embed
-                .title("Artwork request")
+                .title(get_text(lang, "sauce-saucenao-skeb-title", &[]))
                 .author(EmbedAuthorBuilder::new(skeb.creator).url(skeb.author_url)),
             ResData::HMagazines(hmag) => embed
                 .title(hmag.title)
-                .field(EmbedFieldBuilder::new("Part:", hmag.part)),
+                .field(EmbedFieldBuilder::new(
+                    get_text(lang, "sauce-saucenao-hmag-part", &[]),
+                    get_text(
+                        lang,
+                        "sauce-saucenao-hmag-part.value",
+                        &[("type", "volume".into()), ("num", hmag.part.into())],
+                    ),
+                )),
             ResData::Movies(movie) => {
                 let embed = embed
                     .title(format!("{} ({})", movie.source, movie.year))
-                    .field(EmbedFieldBuilder::new("Timestamp:", movie.est_time).inline());
+                    .field(timestamp_field(lang, &movie.est_time).inline());
                 if let Some(part) = movie.part {
-                    embed.field(EmbedFieldBuilder::new("Part:", part).inline())
+                    embed.field(part_field(lang, &part).inline())
                 } else {
                     embed
                 }
@@ -219,20 +478,262 @@ pub async fn build_embed(
                 EmbedAuthorBuilder::new(medi.member_name)
                     .url(format!("https://medibang.com/author/{}", medi.member_id)),
             ),
+            ResData::Unknown { raw, .. } => {
+                // Most indexes have one of these, the footer already says where it's from
+                let title = ["title", "source"]
+                    .iter()
+                    .filter_map(|key| raw.get(key)?.as_str())
+                    .find(|x| !x.is_empty());
+                if let Some(title) = title {
+                    embed.title(title)
+                } else {
+                    embed
+                }
+            }
         };
 
-    Ok((embed, attachment))
+    Ok((embed, image.attachment))
 }
 
-#[derive(Debug, Deserialize, Clone)]
+fn part_field(lang: &LanguageIdentifier, part: &str) -> EmbedFieldBuilder {
+    EmbedFieldBuilder::new(
+        get_text(lang, "sauce-saucenao-part", &[]),
+        get_text(lang, "sauce-saucenao-part.value", &[("num", part.into())]),
+    )
+}
+
+fn timestamp_field(lang: &LanguageIdentifier, timestamp: &str) -> EmbedFieldBuilder {
+    EmbedFieldBuilder::new(
+        get_text(lang, "sauce-saucenao-timestamp", &[]),
+        get_text(
+            lang,
+            "sauce-saucenao-timestamp.value",
+            &[("timestamp", timestamp.into())],
+        ),
+    )
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
 pub struct DataHeader {
     pub status: i32,
+    /// Searches allowed every 30 seconds, SauceNAO sends the limits as strings
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub short_limit: Option<u32>,
+    /// Searches allowed every 24 hours
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub long_limit: Option<u32>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub short_remaining: Option<u32>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    pub long_remaining: Option<u32>,
+    pub message: Option<String>,
+}
+
+fn lenient_number<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(x) => x.as_u64().and_then(|x| x.try_into().ok()),
+        Value::String(x) => x.parse().ok(),
+        _ => None,
+    })
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Data {
     pub header: DataHeader,
+    /// Missing when SauceNAO refuses the search
+    #[serde(default)]
     pub results: Vec<Res>,
+    /// Whether it came from the [`SauceCache`] instead of SauceNAO
+    #[serde(skip)]
+    pub cached: bool,
+}
+
+/// Status SauceNAO answers with when we're out of searches in either window.
+pub const RATE_LIMITED_STATUS: i32 = -2;
+/// Longest a search waits for SauceNAO's limits before getting refused, the interaction
+/// should be deferred by then.
+pub const MAX_QUEUE_WAIT: Duration = Duration::from_secs(10);
+const SHORT_WINDOW: Duration = Duration::from_secs(30);
+const LONG_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug)]
+struct Window {
+    length: Duration,
+    limit: Option<u32>,
+    remaining: Option<u32>,
+    /// When `remaining` goes back to `limit`, guessed from the first search we saw in it
+    reset: Option<Instant>,
+}
+
+impl Window {
+    fn new(length: Duration) -> Self {
+        Window {
+            length,
+            limit: None,
+            remaining: None,
+            reset: None,
+        }
+    }
+
+    fn refresh(&mut self, now: Instant) {
+        if self.reset.map_or(false, |x| x <= now) {
+            self.remaining = self.limit;
+            self.reset = None;
+        }
+    }
+
+    fn retry_after(&self, now: Instant) -> Option<Duration> {
+        (self.remaining == Some(0)).then(|| {
+            self.reset
+                .map_or(self.length, |x| x.saturating_duration_since(now))
+        })
+    }
+
+    fn take(&mut self, now: Instant) {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(1);
+        }
+        self.reset.get_or_insert(now + self.length);
+    }
+
+    fn update(&mut self, limit: Option<u32>, remaining: Option<u32>, now: Instant) {
+        self.refresh(now);
+        if limit.is_some() {
+            self.limit = limit;
+        }
+        if remaining.is_some() {
+            self.remaining = remaining;
+        }
+        self.reset.get_or_insert(now + self.length);
+    }
+}
+
+/// What we know of the searches left in SauceNAO, shared by every search so they can stop
+/// before SauceNAO starts refusing them.
+pub struct RateLimits {
+    windows: Mutex<(Window, Window)>,
+    refused: AtomicU64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        RateLimits {
+            windows: Mutex::new((Window::new(SHORT_WINDOW), Window::new(LONG_WINDOW))),
+            refused: AtomicU64::new(0),
+        }
+    }
+}
+
+impl RateLimits {
+    /// Counts a search if there's one left, otherwise returns how long until there is.
+    pub fn try_take(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (short, long) = &mut *windows;
+        short.refresh(now);
+        long.refresh(now);
+        if let Some(wait) = long.retry_after(now).or_else(|| short.retry_after(now)) {
+            return Err(wait);
+        }
+        short.take(now);
+        long.take(now);
+        Ok(())
+    }
+
+    /// Like [`RateLimits::try_take`] but waits for the next search if it's at most
+    /// `max_wait` away.
+    pub async fn take(&self, max_wait: Duration) -> Result<(), Duration> {
+        let deadline = Instant::now() + max_wait;
+        loop {
+            match self.try_take() {
+                Ok(()) => return Ok(()),
+                Err(wait) if Instant::now() + wait <= deadline => {
+                    sleep(wait.max(Duration::from_millis(100))).await
+                }
+                Err(wait) => {
+                    self.refused.fetch_add(1, Ordering::Relaxed);
+                    return Err(wait);
+                }
+            }
+        }
+    }
+
+    /// How long until the next search, if we're out of them.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (short, long) = &mut *windows;
+        short.refresh(now);
+        long.refresh(now);
+        long.retry_after(now).or_else(|| short.retry_after(now))
+    }
+
+    /// Takes in the limits SauceNAO reported, it also tells us when we got refused.
+    pub fn update(&self, header: &DataHeader) {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (short, long) = &mut *windows;
+        short.update(header.short_limit, header.short_remaining, now);
+        long.update(header.long_limit, header.long_remaining, now);
+        // Refused without the counts telling us why, the message says which window it was
+        if header.status == RATE_LIMITED_STATUS
+            && short.remaining != Some(0)
+            && long.remaining != Some(0)
+        {
+            let daily = header
+                .message
+                .as_deref()
+                .map_or(false, |x| x.contains("Daily"));
+            if daily {
+                long.remaining = Some(0);
+            } else {
+                short.remaining = Some(0);
+            }
+        }
+    }
+
+    /// The limits in the Prometheus text format, unknown ones are left out.
+    pub fn metrics(&self) -> String {
+        let windows = self.windows.lock().unwrap();
+        let (short, long) = &*windows;
+        let mut text = String::new();
+        for (name, help, value) in [
+            (
+                "saucenao_short_limit",
+                "Searches allowed every 30 seconds",
+                short.limit,
+            ),
+            (
+                "saucenao_short_remaining",
+                "Searches left in the 30 seconds window",
+                short.remaining,
+            ),
+            (
+                "saucenao_long_limit",
+                "Searches allowed every 24 hours",
+                long.limit,
+            ),
+            (
+                "saucenao_long_remaining",
+                "Searches left in the 24 hours window",
+                long.remaining,
+            ),
+        ] {
+            if let Some(value) = value {
+                text += &format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n");
+            }
+        }
+        text += &format!(
+            "# HELP saucenao_refused_total Searches refused before reaching SauceNAO\n\
+             # TYPE saucenao_refused_total counter\n\
+             saucenao_refused_total {}\n",
+            self.refused.load(Ordering::Relaxed)
+        );
+        text
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -263,80 +764,61 @@ impl<'de> Deserialize<'de> for Res {
                 .ok_or_else(|| D::Error::missing_field("header"))?,
         )
         .map_err(D::Error::custom)?;
-        let data = value
-            .get("data")
-            .ok_or_else(|| D::Error::missing_field("data"))?;
-        let data = match header.index_id {
-            0 => ResData::HMagazines(
-                SauceHMagazinesData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            5 | 6 => ResData::Pixiv(SaucePixivData::deserialize(data).map_err(D::Error::custom)?),
-            8 => ResData::NicoNico(SauceNicoNicoData::deserialize(data).map_err(D::Error::custom)?),
-            9 => ResData::Danbooru(SauceDanbooruData::deserialize(data).map_err(D::Error::custom)?),
-            10 => ResData::Drawr(SauceDrawrData::deserialize(data).map_err(D::Error::custom)?),
-            11 => ResData::NijieImages(
-                SauceNijieImagesData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            12 => ResData::Yandere(SauceYandereData::deserialize(data).map_err(D::Error::custom)?),
-            16 => ResData::FAKKU(SauceFAKKUData::deserialize(data).map_err(D::Error::custom)?),
-            18 | 38 => {
-                ResData::EHentai(SauceEHentaiData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            19 => {
-                ResData::Market2D(Sauce2DMarketData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            20 => {
-                ResData::MediBang(SauceMediBangData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            21 | 22 => ResData::Anime(SauceAnimeData::deserialize(data).map_err(D::Error::custom)?),
-            23 => ResData::Movies(SauceMovieData::deserialize(data).map_err(D::Error::custom)?),
-            24 => ResData::Shows(SauceShowData::deserialize(data).map_err(D::Error::custom)?),
-            25 => {
-                ResData::Gelbooru(SauceGelbooruData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            26 => {
-                ResData::Konachan(SauceKonachanData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            27 => ResData::Sankaku(SauceSankakuData::deserialize(data).map_err(D::Error::custom)?),
-            28 => ResData::AnimePictures(
-                SauceAnimePicturesData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            29 => ResData::E621(SauceE621Data::deserialize(data).map_err(D::Error::custom)?),
-            30 => ResData::IdolComplex(
-                SauceIdolComplexData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            31 => ResData::Bcy(SauceBcyIllustData::deserialize(data).map_err(D::Error::custom)?),
-            32 => {
-                ResData::BcyCoser(SauceBcyCosplayData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            34 => ResData::DeviantArt(
-                SauceDeviantArtData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            35 => ResData::Pawoo(SaucePawooData::deserialize(data).map_err(D::Error::custom)?),
-            36 => {
-                ResData::Madokami(SauceMadokamiData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            37 | 371 => {
-                ResData::Mangadex(SauceMangadexData::deserialize(data).map_err(D::Error::custom)?)
-            }
-            39 => ResData::Artstation(
-                SauceArtstationData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            40 => ResData::FurAffinity(
-                SauceFurAffinityData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            41 => ResData::Twitter(SauceTwitterData::deserialize(data).map_err(D::Error::custom)?),
-            42 => ResData::FurryNetwork(
-                SauceFurryNetworkData::deserialize(data).map_err(D::Error::custom)?,
-            ),
-            43 => ResData::Kemono(SauceKemonoData::deserialize(data).map_err(D::Error::custom)?),
-            44 => ResData::Skeb(SauceSkebData::deserialize(data).map_err(D::Error::custom)?),
-            _ => return Err(D::Error::unknown_variant(&header.index_name, &[])),
+        let data = value.get("data").unwrap_or(&Value::Null);
+        let parsed = match header.index_id {
+            0 => parse(data, ResData::HMagazines),
+            5 | 6 => parse(data, ResData::Pixiv),
+            8 => parse(data, ResData::NicoNico),
+            9 => parse(data, ResData::Danbooru),
+            10 => parse(data, ResData::Drawr),
+            11 => parse(data, ResData::NijieImages),
+            12 => parse(data, ResData::Yandere),
+            16 => parse(data, ResData::FAKKU),
+            18 | 38 => parse(data, ResData::EHentai),
+            19 => parse(data, ResData::Market2D),
+            20 => parse(data, ResData::MediBang),
+            21 | 22 => parse(data, ResData::Anime),
+            23 => parse(data, ResData::Movies),
+            24 => parse(data, ResData::Shows),
+            25 => parse(data, ResData::Gelbooru),
+            26 => parse(data, ResData::Konachan),
+            27 => parse(data, ResData::Sankaku),
+            28 => parse(data, ResData::AnimePictures),
+            29 => parse(data, ResData::E621),
+            30 => parse(data, ResData::IdolComplex),
+            31 => parse(data, ResData::Bcy),
+            32 => parse(data, ResData::BcyCoser),
+            34 => parse(data, ResData::DeviantArt),
+            35 => parse(data, ResData::Pawoo),
+            36 => parse(data, ResData::Madokami),
+            37 | 371 => parse(data, ResData::Mangadex),
+            39 => parse(data, ResData::Artstation),
+            40 => parse(data, ResData::FurAffinity),
+            41 => parse(data, ResData::Twitter),
+            42 => parse(data, ResData::FurryNetwork),
+            43 => parse(data, ResData::Kemono),
+            44 => parse(data, ResData::Skeb),
+            _ => return Ok(Res { data: ResData::unknown(&header, data), header }),
         };
+        // A single result changing its schema shouldn't take down the whole search
+        let data = parsed.unwrap_or_else(|err| {
+            eprintln!(
+                "SauceNAO result from {} doesn't match its schema: {err}",
+                header.index_name
+            );
+            ResData::unknown(&header, data)
+        });
         Ok(Res { header, data })
     }
 }
 
+fn parse<T: DeserializeOwned>(
+    data: &Value,
+    variant: fn(T) -> ResData,
+) -> Result<ResData, serde_json::Error> {
+    T::deserialize(data).map(variant)
+}
+
 #[derive(Debug, Clone)]
 pub enum ResData {
     HMagazines(SauceHMagazinesData),
@@ -371,9 +853,26 @@ pub enum ResData {
     Konachan(SauceKonachanData),
     Market2D(Sauce2DMarketData),
     Shows(SauceShowData),
+    /// An index we don't know about yet, or one whose data didn't match its struct
+    Unknown {
+        index_name: String,
+        ext_urls: Vec<String>,
+        raw: Value,
+    },
 }
 
 impl ResData {
+    fn unknown(header: &ResHeader, data: &Value) -> Self {
+        Self::Unknown {
+            index_name: header.index_name.clone(),
+            ext_urls: data
+                .get("ext_urls")
+                .and_then(|x| Vec::<String>::deserialize(x).ok())
+                .unwrap_or_default(),
+            raw: data.clone(),
+        }
+    }
+
     pub fn is_nsfw_site(&self) -> bool {
         match self {
             Self::HMagazines(_)
@@ -407,7 +906,9 @@ impl ResData {
             | Self::Twitter(_)
             | Self::Drawr(_)
             | Self::Yandere(_)
-            | Self::Shows(_) => false,
+            | Self::Shows(_)
+            // The hidden flag of the result is all we have
+            | Self::Unknown { .. } => false,
         }
     }
 
@@ -444,7 +945,8 @@ impl ResData {
             | Self::Konachan(SauceKonachanData { ext_urls, .. })
             | Self::Market2D(Sauce2DMarketData { ext_urls, .. })
             | Self::BcyCoser(SauceBcyCosplayData { ext_urls, .. })
-            | Self::Shows(SauceShowData { ext_urls, ..}) => Some(ext_urls),
+            | Self::Shows(SauceShowData { ext_urls, ..})
+            | Self::Unknown { ext_urls, .. } => Some(ext_urls),
         }
     }
 }
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use bonsaidb::{core::keyvalue::AsyncKeyValue, local::AsyncDatabase};
+use serde::{Deserialize, Serialize};
+use twilight_model::id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+};
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    censor::{Censor, CensorMethod, NsfwPolicy, DEFAULT_STRENGTH},
+    i18n::AVAILABLE_LANGS,
+};
+
+/// Settings chosen by the admins of a guild, stored in bonsai.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    /// Default language for everyone in the guild
+    pub lang: Option<String>,
+    /// How NSFW images get censored outside NSFW channels
+    pub censor_method: Option<CensorMethod>,
+    /// Between 1 and 100
+    pub censor_strength: Option<u8>,
+    /// What happens to NSFW images outside NSFW channels
+    pub nsfw_policy: Option<NsfwPolicy>,
+    /// Whether results from adult sites count as NSFW
+    pub nsfw_sites: Option<bool>,
+}
+
+impl GuildSettings {
+    fn key(guild_id: Id<GuildMarker>) -> String {
+        format!("guild_settings_{guild_id}")
+    }
+
+    pub async fn get(bonsai: &AsyncDatabase, guild_id: Id<GuildMarker>) -> Result<Self> {
+        Ok(bonsai
+            .get_key(Self::key(guild_id))
+            .into::<Self>()
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn save(&self, bonsai: &AsyncDatabase, guild_id: Id<GuildMarker>) -> Result<()> {
+        bonsai.set_key(Self::key(guild_id), self).await?;
+        Ok(())
+    }
+
+    pub fn lang(&self) -> Option<&'static LanguageIdentifier> {
+        self.lang
+            .as_deref()
+            .and_then(|lang| AVAILABLE_LANGS.get(lang).copied())
+    }
+
+    /// The defaults fill in whatever wasn't chosen.
+    pub fn censor(&self) -> Censor {
+        Censor {
+            method: self.censor_method.unwrap_or_default(),
+            strength: self.censor_strength.unwrap_or(DEFAULT_STRENGTH),
+            policy: self.nsfw_policy.unwrap_or_default(),
+            nsfw_sites: self.nsfw_sites.unwrap_or(false),
+        }
+    }
+}
+
+/// Settings chosen by a user, these take priority over the guild ones.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    /// Language override for the user
+    pub lang: Option<String>,
+}
+
+impl UserSettings {
+    fn key(user_id: Id<UserMarker>) -> String {
+        format!("user_settings_{user_id}")
+    }
+
+    pub async fn get(bonsai: &AsyncDatabase, user_id: Id<UserMarker>) -> Result<Self> {
+        Ok(bonsai
+            .get_key(Self::key(user_id))
+            .into::<Self>()
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn save(&self, bonsai: &AsyncDatabase, user_id: Id<UserMarker>) -> Result<()> {
+        bonsai.set_key(Self::key(user_id), self).await?;
+        Ok(())
+    }
+
+    pub fn lang(&self) -> Option<&'static LanguageIdentifier> {
+        self.lang
+            .as_deref()
+            .and_then(|lang| AVAILABLE_LANGS.get(lang).copied())
+    }
+}
@@ -0,0 +1,96 @@
+use std::{collections::BTreeMap, sync::RwLock, time::Duration};
+
+use anyhow::{Context, Result};
+use twilight_gateway::{stream, Config, ConnectionStatus, Event, Shard};
+use twilight_http::Client as HttpClient;
+
+use crate::config::ShardRange;
+
+/// What we last saw of a shard, updated from the gateway event loop.
+#[derive(Clone, Debug)]
+pub struct ShardInfo {
+    pub status: ConnectionStatus,
+    pub latency: Option<Duration>,
+    /// Times the shard got a new or resumed session after the first one
+    pub reconnects: u32,
+    sessions: u32,
+}
+
+#[derive(Default)]
+pub struct ShardStats {
+    shards: RwLock<BTreeMap<u64, ShardInfo>>,
+}
+
+impl ShardStats {
+    pub fn update(&self, shard: &Shard, event: Option<&Event>) {
+        let id = shard.id().number();
+        let mut shards = self.shards.write().unwrap();
+        let info = shards.entry(id).or_insert_with(|| ShardInfo {
+            status: shard.status().clone(),
+            latency: None,
+            reconnects: 0,
+            sessions: 0,
+        });
+        info.status = shard.status().clone();
+        info.latency = shard.latency().average();
+
+        match event {
+            Some(Event::Ready(_)) | Some(Event::Resumed) => {
+                info.sessions += 1;
+                if info.sessions > 1 {
+                    info.reconnects += 1;
+                    println!("Shard {id} reconnected ({} times so far)", info.reconnects);
+                } else {
+                    println!("Shard {id} is ready");
+                }
+            }
+            Some(Event::GatewayReconnect) => println!("Shard {id} was asked to reconnect"),
+            _ => {}
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<ShardInfo> {
+        self.shards.read().unwrap().get(&id).cloned()
+    }
+
+    /// A line per shard with its status, average latency and reconnects.
+    pub fn summary(&self) -> String {
+        self.shards
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, info)| {
+                let latency = info
+                    .latency
+                    .map(|x| format!("{}ms", x.as_millis()))
+                    .unwrap_or_else(|| "unknown".to_string());
+                format!(
+                    "Shard {id}: {:?}, latency {latency}, {} reconnects",
+                    info.status, info.reconnects
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Starts the shards this process is in charge of, `range` is used when the bot is split
+/// between several processes, otherwise every shard Discord recommends is started here.
+pub async fn create_shards(
+    http: &HttpClient,
+    config: Config,
+    range: Option<ShardRange>,
+) -> Result<Vec<Shard>> {
+    let shards = if let Some(ShardRange { start, end, total }) = range {
+        println!("Starting shards {start}..{end} out of {total}");
+        stream::create_range(start..end, total, config, |_, builder| builder.build()).collect()
+    } else {
+        let shards = stream::create_recommended(http, config, |_, builder| builder.build())
+            .await
+            .context("Couldn't get the recommended shard count")?
+            .collect::<Vec<_>>();
+        println!("Starting {} recommended shards", shards.len());
+        shards
+    };
+    Ok(shards)
+}
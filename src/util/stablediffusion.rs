@@ -1,21 +1,22 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use once_cell::sync::Lazy;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-static API_URL: Lazy<String> = Lazy::new(|| std::env::var("STABLE_DIFFUSION").unwrap());
+use super::web::{Service, WebClient};
+
+const GENERATION_TIMEOUT: Duration = Duration::from_secs(300);
 
-pub async fn get_txt2img(req: &Txt2Img) -> Result<Txt2ImgRes> {
+pub async fn get_txt2img(web: &WebClient, req: &Txt2Img) -> Result<Txt2ImgRes> {
     let body = serde_json::to_string(&req)?;
-    let client = reqwest::Client::new();
-    let res = client
-        .post(format!("{}/sdapi/v1/txt2img", API_URL.as_str()))
+    let request = web
+        .post(web.url(Service::StableDiffusion, "/sdapi/v1/txt2img")?)
         .body(body)
         .header("Content-Type", "application/json")
-        .send()
-        .await?
-        .json::<Txt2ImgRes>()
-        .await?;
+        // Generating takes way longer than any other request
+        .timeout(GENERATION_TIMEOUT);
+    let res = web.send(request).await?.json::<Txt2ImgRes>().await?;
     Ok(res)
 }
 
@@ -2,97 +2,292 @@ use anyhow::Result;
 use sentry::{add_breadcrumb, Breadcrumb, Level};
 use serde::Deserialize;
 use twilight_model::{
-    channel::{message::embed::EmbedField, Attachment},
-    http::attachment::Attachment as HttpAttachment,
+    channel::message::embed::EmbedField, http::attachment::Attachment as HttpAttachment,
 };
-use twilight_util::builder::embed::{EmbedBuilder, ImageSource};
+use twilight_util::builder::embed::{EmbedBuilder, EmbedFooterBuilder};
+use unic_langid::LanguageIdentifier;
 
-use super::{censor_image, get_bytes, seconds_to_timestamp};
+use super::{
+    cache::SauceCache,
+    censor::{Censor, NsfwPolicy},
+    i18n::get_text,
+    seconds_to_timestamp, shortify,
+    web::{Service, WebClient, MAX_DOWNLOAD_SIZE},
+    SauceImage,
+};
 
-pub async fn fetch(attachment: &Attachment) -> Result<TraceResponse> {
+/// Searches in trace.moe unless the image is cached. Linked images are searched by their
+/// link so trace.moe downloads them itself, the rest get uploaded.
+pub async fn fetch(
+    web: &WebClient,
+    cache: &SauceCache,
+    image: &SauceImage,
+    options: &SearchOptions,
+) -> Result<TraceResponse> {
     add_breadcrumb(Breadcrumb {
         category: Some("trace.moe".into()),
-        message: Some(format!(
-            "Searching in trace.moe about {} ",
-            attachment.proxy_url.clone()
-        )),
+        message: Some(format!("Searching in trace.moe about {} ", image.url)),
         level: Level::Info,
         ..Default::default()
     });
 
-    let client = reqwest::Client::new();
-    let res = client
-        .post("https://api.trace.moe/search?anilistInfo")
-        .body(get_bytes(&attachment.proxy_url).await?)
-        .header(
-            "Content-Type",
-            attachment
-                .content_type
-                .as_deref()
-                .unwrap_or("application/x-www-form-urlencoded"),
-        )
-        .send()
-        .await?
-        .json::<TraceResponse>()
-        .await?;
+    // Linked images are downloaded by trace.moe itself, here only the cache needs them
+    let bytes = if image.linked && !cache.enabled() {
+        None
+    } else {
+        Some(web.get_bytes_limited(&image.url, MAX_DOWNLOAD_SIZE).await?)
+    };
+    let hashes = match &bytes {
+        Some(bytes) => cache.hashes(bytes).await?,
+        None => None,
+    };
+    let mut query = vec![("anilistInfo", String::new())];
+    if options.cut_borders {
+        query.push(("cutBorders", String::new()));
+    }
+    if let Some(id) = options.anilist_id {
+        query.push(("anilistID", id.to_string()));
+    }
+    let params = query
+        .iter()
+        .map(|(key, value)| {
+            if value.is_empty() {
+                key.to_string()
+            } else {
+                format!("{key}={value}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    if let Some(text) = cache.get("tracemoe", &params, hashes.as_ref()).await? {
+        let mut res = serde_json::from_str::<TraceResponse>(&text)?;
+        res.cached = true;
+        return Ok(res);
+    }
+
+    let request = match bytes {
+        Some(bytes) if !image.linked => web
+            .post(web.url(Service::TraceMoe, "/search")?)
+            .query(&query)
+            .body(bytes)
+            .header(
+                "Content-Type",
+                image
+                    .content_type
+                    .as_deref()
+                    .unwrap_or("application/x-www-form-urlencoded"),
+            ),
+        _ => {
+            query.push(("url", image.url.clone()));
+            web.get(web.url(Service::TraceMoe, "/search")?)
+                .query(&query)
+        }
+    };
+    // Errors come with a JSON body too, like when the image can't be read
+    let text = web.send(request).await?.text().await?;
+    let res = serde_json::from_str::<TraceResponse>(&text)?;
+    if res.error.is_empty() {
+        if let Err(err) = cache.set("tracemoe", &params, hashes.as_ref(), &text).await {
+            eprintln!("Couldn't cache the trace.moe search: {err}");
+        }
+    }
     Ok(res)
 }
 
+/// How many searches are left this month for the bot's IP or API key.
+pub async fn quota(web: &WebClient) -> Result<TraceQuota> {
+    let request = web.get(web.url(Service::TraceMoe, "/me")?);
+    Ok(web
+        .send(request)
+        .await?
+        .error_for_status()?
+        .json::<TraceQuota>()
+        .await?)
+}
+
+/// How trace.moe searches, every option changes the results so they're cached apart.
+#[derive(Clone, Debug, Default)]
+pub struct SearchOptions {
+    /// Removes the black borders of the image before searching
+    pub cut_borders: bool,
+    /// Only looks in the anime with this AniList id
+    pub anilist_id: Option<u32>,
+}
+
+/// The footer says if `res` was cached, so it's known the result could be outdated, and the
+/// searches left when its quota is known. With `clip` the preview of the scene is attached
+/// instead of the still, unless it's from an adult anime outside a NSFW channel and the
+/// guild censors or leaves out those, since videos can't be censored.
 pub async fn build_embed(
+    web: &WebClient,
     data: &TraceResult,
     nsfw_channel: bool,
-) -> Result<(EmbedBuilder, HttpAttachment)> {
+    censor: Censor,
+    res: &TraceResponse,
+    clip: bool,
+    lang: &LanguageIdentifier,
+) -> Result<(EmbedBuilder, Option<HttpAttachment>)> {
+    let from = seconds_to_timestamp(data.from as u32);
+    let to = seconds_to_timestamp(data.to as u32);
+    let title = data
+        .anilist
+        .title
+        .english
+        .as_ref()
+        .unwrap_or(&data.anilist.title.romaji);
+    let anilist = format!("https://anilist.co/anime/{}/", data.anilist.id);
     let mut embed = EmbedBuilder::new()
-        .title(
-            data.anilist
-                .title
-                .english
-                .as_ref()
-                .unwrap_or(&data.anilist.title.romaji),
-        )
-        .url(format!("https://anilist.co/anime/{}/", data.anilist.id))
+        .title(title)
+        .url(&anilist)
         .color(0x0)
-        .image(ImageSource::attachment("trace.png")?)
         .field(EmbedField {
-            name: "Similarity".to_string(),
-            value: format!("{:.2}%", data.similarity * 100f64),
+            name: get_text(lang, "sauce-tracemoe-similarity", &[]),
+            value: get_text(
+                lang,
+                "sauce-tracemoe-similarity.value",
+                &[("amount", format!("{:.2}", data.similarity * 100f64).into())],
+            ),
             inline: true,
         })
         .field(EmbedField {
-            name: "Timestamp".to_string(),
+            name: get_text(lang, "sauce-tracemoe-timestamp", &[]),
             value: data.episode.as_ref().map_or_else(
-                || seconds_to_timestamp(data.from as u32),
+                || {
+                    get_text(
+                        lang,
+                        "sauce-tracemoe-timestamp.value",
+                        &[("from", from.as_str().into()), ("to", to.as_str().into())],
+                    )
+                },
                 |ep| {
-                    format!(
-                        "Episode {} at {}",
-                        ep.to_string(),
-                        seconds_to_timestamp(data.from as u32)
+                    let key = match ep {
+                        EpisodeField::Range(_) => "sauce-tracemoe-timestamp.episodes",
+                        _ => "sauce-tracemoe-timestamp.episode",
+                    };
+                    get_text(
+                        lang,
+                        key,
+                        &[
+                            ("num", ep.to_string().into()),
+                            ("from", from.as_str().into()),
+                            ("to", to.as_str().into()),
+                        ],
                     )
                 },
             ),
             inline: true,
         });
-    let image = get_bytes(&data.image).await?;
-    let attachment = if data.anilist.is_adult && !nsfw_channel {
-        embed = embed.description("**WARNING**: Image is NSFW so it's been censored!");
-        HttpAttachment::from_bytes(
-            "trace.png".to_string(),
-            censor_image(image).await?.into(),
-            1,
-        )
-    } else {
-        HttpAttachment::from_bytes("trace.png".to_string(), image.into(), 1)
-    };
+    // The other titles it's known by, without repeating the one in the title
+    let mut aliases: Vec<&str> = Vec::new();
+    let titles = data.anilist.title.native.iter();
+    for alias in titles
+        .chain([&data.anilist.title.romaji])
+        .chain(&data.anilist.synonyms)
+    {
+        if alias != title && !aliases.contains(&alias.as_str()) {
+            aliases.push(alias);
+        }
+    }
+    if !aliases.is_empty() {
+        embed = embed.field(EmbedField {
+            name: get_text(lang, "sauce-tracemoe-aliases", &[]),
+            value: shortify(&aliases.join("\n"), 1024).to_string(),
+            inline: false,
+        });
+    }
+    if let Some(id) = data.anilist.id_mal {
+        embed = embed.field(EmbedField {
+            name: get_text(lang, "sauce-tracemoe-links", &[]),
+            value: format!(
+                "[AniList]({anilist}) · [MyAnimeList](https://myanimelist.net/anime/{id}/)"
+            ),
+            inline: false,
+        });
+    }
+    let footer = [
+        res.cached.then(|| get_text(lang, "sauce-cached", &[])),
+        res.quota.as_ref().map(|quota| {
+            get_text(
+                lang,
+                "sauce-tracemoe-quota",
+                &[("left", quota.left().into())],
+            )
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if !footer.is_empty() {
+        embed = embed.footer(EmbedFooterBuilder::new(footer.join(" · ")));
+    }
+    let nsfw = data.anilist.is_adult && !nsfw_channel;
+    let policy = censor.policy_for(nsfw);
+    if clip && matches!(policy, NsfwPolicy::Allow | NsfwPolicy::Spoiler) {
+        // Discord shows it above the embed, so there's no image in it
+        let video = web
+            .get_bytes_limited(&data.video, MAX_DOWNLOAD_SIZE)
+            .await?;
+        if let Some(warning) = policy.warning() {
+            embed = embed.description(get_text(lang, warning, &[]));
+        }
+        let attachment = HttpAttachment::from_bytes(policy.file_name("trace.mp4"), video.into(), 1);
+        return Ok((embed, Some(attachment)));
+    }
+
+    let image = censor.attach(web, &data.image, "trace", nsfw).await?;
+    if let Some(source) = image.source()? {
+        embed = embed.image(source);
+    }
+    if let Some(warning) = image.warning {
+        let mut description = get_text(lang, warning, &[]);
+        if clip {
+            description = format!(
+                "{description}\n{}",
+                get_text(lang, "sauce-tracemoe-clip-nsfw", &[])
+            );
+        }
+        embed = embed.description(description);
+    }
 
-    Ok((embed, attachment))
+    Ok((embed, image.attachment))
 }
 
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TraceResponse {
-    pub frame_count: u32,
+    /// How many frames were compared
+    #[serde(default)]
+    pub frame_count: u64,
+    /// Empty unless the search failed, errors don't have the rest of the fields
+    #[serde(default)]
     pub error: String,
+    #[serde(default)]
     pub result: Vec<TraceResult>,
+    /// Whether it came from the [`SauceCache`] instead of trace.moe
+    #[serde(skip)]
+    pub cached: bool,
+    /// Only known if it was asked with [`quota`] after searching
+    #[serde(skip)]
+    pub quota: Option<TraceQuota>,
+}
+
+/// What `/me` says about the searches of the bot.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceQuota {
+    /// The IP or the email of the API key
+    pub id: String,
+    pub priority: u32,
+    pub concurrency: u32,
+    /// Searches per month
+    pub quota: u64,
+    pub quota_used: u64,
+}
+
+impl TraceQuota {
+    pub fn left(&self) -> u64 {
+        self.quota.saturating_sub(self.quota_used)
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -130,7 +325,10 @@ impl ToString for EpisodeField {
         match self {
             Self::Name(str) => str.to_owned(),
             Self::Episode(num) => num.to_string(),
-            Self::Range(vec) => format!("{} - {}", vec[0], vec[vec.len() - 1]),
+            Self::Range(vec) => match (vec.first(), vec.last()) {
+                (Some(first), Some(last)) => format!("{first}–{last}"),
+                _ => String::new(),
+            },
         }
     }
 }
@@ -0,0 +1,418 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{ensure, Context, Result};
+use bytes::{Bytes, BytesMut};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::RETRY_AFTER,
+    redirect::Policy,
+    Client, ClientBuilder, Request, RequestBuilder, Response, StatusCode, Url,
+};
+use sentry::{add_breadcrumb, Breadcrumb, Level};
+use tokio::{
+    net::lookup_host,
+    time::{sleep, Instant},
+};
+
+use crate::config::Config;
+
+/// Longest Retry-After we are willing to wait for before retrying.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Biggest file downloaded on behalf of users, the same Discord allows to upload.
+pub const MAX_DOWNLOAD_SIZE: usize = 25 * 1024 * 1024;
+
+/// Redirects followed when downloading a file a user linked.
+const MAX_REDIRECTS: usize = 5;
+
+/// The file is bigger than the limit it was downloaded with.
+#[derive(Debug)]
+pub struct TooLarge {
+    pub limit: usize,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The file is bigger than {} bytes", self.limit)
+    }
+}
+
+impl Error for TooLarge {}
+
+/// Upstream services we talk to, each one with its own base URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Service {
+    SauceNao,
+    TraceMoe,
+    Jisho,
+    Yandex,
+    AnimeThemes,
+    StableDiffusion,
+    Iqdb,
+    Ascii2d,
+    AniList,
+}
+
+impl Service {
+    pub const ALL: [Service; 9] = [
+        Service::SauceNao,
+        Service::TraceMoe,
+        Service::Jisho,
+        Service::Yandex,
+        Service::AnimeThemes,
+        Service::StableDiffusion,
+        Service::Iqdb,
+        Service::Ascii2d,
+        Service::AniList,
+    ];
+
+    /// Name used for its `[services]` key and its path in the mock server.
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::SauceNao => "saucenao",
+            Service::TraceMoe => "tracemoe",
+            Service::Jisho => "jisho",
+            Service::Yandex => "yandex",
+            Service::AnimeThemes => "animethemes",
+            Service::StableDiffusion => "stable_diffusion",
+            Service::Iqdb => "iqdb",
+            Service::Ascii2d => "ascii2d",
+            Service::AniList => "anilist",
+        }
+    }
+
+    fn default_base_url(self) -> Option<&'static str> {
+        match self {
+            Service::SauceNao => Some("https://saucenao.com"),
+            Service::TraceMoe => Some("https://api.trace.moe"),
+            Service::Jisho => Some("https://jisho.org"),
+            Service::Yandex => Some("https://yandex.ru"),
+            Service::AnimeThemes => Some("https://api.animethemes.moe"),
+            Service::StableDiffusion => None,
+            Service::Iqdb => Some("https://iqdb.org"),
+            Service::Ascii2d => Some("https://ascii2d.net"),
+            Service::AniList => Some("https://graphql.anilist.co"),
+        }
+    }
+}
+
+/// The HTTP client shared by every command, it retries 5xx and 429 responses with backoff
+/// and identifies itself with the configured User-Agent.
+#[derive(Clone)]
+pub struct WebClient {
+    client: Client,
+    /// Used for the files users link, it can only connect to public addresses
+    downloads: Client,
+    base_urls: Arc<HashMap<Service, String>>,
+    retries: u32,
+    /// The mock server is on localhost, so user links can point there while testing
+    allow_local: bool,
+}
+
+impl WebClient {
+    pub fn new(config: &Config) -> Result<Self> {
+        let builder = || {
+            Client::builder()
+                .user_agent(&config.http.user_agent)
+                .timeout(Duration::from_secs(config.http.timeout))
+                .connect_timeout(Duration::from_secs(config.http.connect_timeout))
+        };
+        let client = builder().build()?;
+        let allow_local = config.mock.is_some();
+        let downloads = if allow_local {
+            client.clone()
+        } else {
+            public_only(builder()).build()?
+        };
+
+        // The mock server goes first, then the overrides and lastly the real services
+        let mut base_urls = HashMap::new();
+        for service in Service::ALL {
+            let url = if service == Service::StableDiffusion {
+                config.stable_diffusion.as_ref().map(|x| x.url.clone())
+            } else {
+                config
+                    .services
+                    .get(service.name())
+                    .cloned()
+                    .or_else(|| service.default_base_url().map(str::to_string))
+            };
+            if let Some(url) = url {
+                let url = match &config.mock {
+                    Some(mock) => format!("http://{}/{}", mock.addr, service.name()),
+                    None => url,
+                };
+                base_urls.insert(service, url);
+            }
+        }
+
+        Ok(WebClient {
+            client,
+            downloads,
+            base_urls: Arc::new(base_urls),
+            retries: config.http.retries,
+            allow_local,
+        })
+    }
+
+    /// Joins `path` to the base URL of the service.
+    pub fn url(&self, service: Service, path: &str) -> Result<String> {
+        let base = self
+            .base_urls
+            .get(&service)
+            .with_context(|| format!("There's no base URL for {service:?}"))?;
+        Ok(format!("{}{path}", base.trim_end_matches('/')))
+    }
+
+    pub fn get(&self, url: impl AsRef<str>) -> RequestBuilder {
+        self.client.get(url.as_ref())
+    }
+
+    pub fn post(&self, url: impl AsRef<str>) -> RequestBuilder {
+        self.client.post(url.as_ref())
+    }
+
+    /// Sends the request, retrying on 5xx, 429, timeouts and connection errors.
+    /// Requests with a streamed body (like multipart forms) can't be cloned so they're sent once.
+    pub async fn send(&self, builder: RequestBuilder) -> Result<Response> {
+        self.send_retrying(builder, true).await
+    }
+
+    /// Like [`WebClient::send`] but a 429 is handed back right away, for services where it
+    /// means the searches ran out and asking again only makes it worse.
+    pub async fn send_rate_limited(&self, builder: RequestBuilder) -> Result<Response> {
+        self.send_retrying(builder, false).await
+    }
+
+    async fn send_retrying(&self, builder: RequestBuilder, retry_429: bool) -> Result<Response> {
+        // Sent by the client that made it, user downloads have their own
+        let (client, request) = builder.build_split();
+        let mut request = request?;
+        let mut attempt = 0;
+        loop {
+            let retry = if attempt < self.retries {
+                request.try_clone()
+            } else {
+                None
+            };
+            let result = execute(&client, request).await;
+            let delay = match (&result, &retry) {
+                // Not worth waiting if the service wants us gone for longer
+                (Ok(res), Some(_))
+                    if is_retryable(res.status(), retry_429)
+                        && retry_after(res).map_or(true, |x| x <= MAX_BACKOFF) =>
+                {
+                    retry_after(res).unwrap_or_else(|| backoff(attempt))
+                }
+                (Err(err), Some(_)) if err.is_timeout() || err.is_connect() => backoff(attempt),
+                // Without the URL since the query can have API keys in it
+                _ => return Ok(result.map_err(reqwest::Error::without_url)?),
+            };
+            sleep(delay).await;
+            request = retry.unwrap();
+            attempt += 1;
+        }
+    }
+
+    pub async fn get_bytes(&self, url: &str) -> Result<Bytes> {
+        Ok(self.send(self.get(url)).await?.bytes().await?)
+    }
+
+    /// Like [`WebClient::get_bytes`] but fails with [`TooLarge`] once the body goes over
+    /// `limit`, for files anyone could have linked. Links that aren't public are refused.
+    pub async fn get_bytes_limited(&self, url: &str, limit: usize) -> Result<Bytes> {
+        ensure!(
+            self.is_public_url(&Url::parse(url)?),
+            "Refusing to download {url}, it isn't public"
+        );
+        let mut res = self
+            .send(self.downloads.get(url))
+            .await?
+            .error_for_status()?;
+        if res.content_length().map_or(false, |x| x > limit as u64) {
+            return Err(TooLarge { limit }.into());
+        }
+        // The length can be missing or lie, so it's checked while reading too
+        let mut bytes = BytesMut::new();
+        while let Some(chunk) = res.chunk().await? {
+            if bytes.len() + chunk.len() > limit {
+                return Err(TooLarge { limit }.into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes.freeze())
+    }
+
+    /// Whether a link given by a user is fine to download, it has to be HTTP and can't point
+    /// to the network the bot runs in. Names are checked once they get resolved, right before
+    /// connecting to them.
+    pub fn is_public_url(&self, url: &Url) -> bool {
+        if self.allow_local {
+            return matches!(url.scheme(), "http" | "https") && url.host_str().is_some();
+        }
+        is_public_host(url)
+    }
+}
+
+/// Makes the client refuse to connect anywhere that isn't public, even after a redirect or
+/// when a name resolves to somewhere else the second time.
+fn public_only(builder: ClientBuilder) -> ClientBuilder {
+    builder
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(Policy::custom(|attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("Too many redirects")
+            } else if is_public_host(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.error("Redirected to an address that isn't public")
+            }
+        }))
+}
+
+/// Resolves names like the system does, failing if any of the addresses isn't public.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(resolve_public(name))
+    }
+}
+
+async fn resolve_public(name: Name) -> Result<Addrs, Box<dyn Error + Send + Sync>> {
+    let addrs: Vec<SocketAddr> = lookup_host((name.as_str(), 0)).await?.collect();
+    if addrs.is_empty() || !addrs.iter().all(|x| is_public_ip(x.ip())) {
+        return Err(format!("{name} doesn't resolve to public addresses").into());
+    }
+    Ok(Box::new(addrs.into_iter()))
+}
+
+/// The part of the check that can be done without resolving the host: the scheme, IP
+/// literals and the names that always mean this machine.
+fn is_public_host(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_lowercase();
+            host != "localhost" && !host.ends_with(".localhost")
+        }
+    }
+}
+
+/// Whether the address is reachable from the internet, anything private, reserved or
+/// otherwise special isn't.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network", 0.0.0.0/8
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved, 240.0.0.0/4 with the broadcast address
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // The ones with an IPv4 address inside go wherever that one goes:
+    // mapped (::ffff:0:0/96), NAT64 (64:ff9b::/96) and 6to4 (2002::/16)
+    if let Some(ipv4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(ipv4);
+    }
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+    }
+    if segments[0] == 0x2002 {
+        let [_, _, a, b, c, d, ..] = ip.octets();
+        return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+    }
+    !(ip.is_multicast()
+        // Unspecified, loopback and the deprecated IPv4-compatible ones, ::/96
+        || segments[..6] == [0; 6]
+        // Unique local, fc00::/7
+        || segments[0] & 0xfe00 == 0xfc00
+        // Link-local, fe80::/10, and the deprecated site-local, fec0::/10
+        || segments[0] & 0xffc0 == 0xfe80
+        || segments[0] & 0xffc0 == 0xfec0
+        // Discard-only, 100::/64
+        || segments[..4] == [0x100, 0, 0, 0]
+        // Documentation, 2001:db8::/32
+        || segments[..2] == [0x2001, 0xdb8]
+        // IETF protocol assignments with Teredo, 2001::/23
+        || (segments[0] == 0x2001 && segments[1] < 0x200))
+}
+
+async fn execute(client: &Client, request: Request) -> reqwest::Result<Response> {
+    let method = request.method().clone();
+    // The query is left out since it can have API keys in it
+    let url = {
+        let url = request.url();
+        format!("{}{}", url.host_str().unwrap_or_default(), url.path())
+    };
+    let start = Instant::now();
+    let result = client.execute(request).await;
+    let elapsed = start.elapsed().as_millis();
+    add_breadcrumb(Breadcrumb {
+        category: Some("http".into()),
+        message: Some(match &result {
+            Ok(res) => format!("{method} {url} {} in {elapsed}ms", res.status()),
+            Err(_) => format!("{method} {url} failed in {elapsed}ms"),
+        }),
+        level: if result.is_ok() {
+            Level::Info
+        } else {
+            Level::Warning
+        },
+        ..Default::default()
+    });
+    result
+}
+
+fn is_retryable(status: StatusCode, retry_429: bool) -> bool {
+    status.is_server_error() || (retry_429 && status == StatusCode::TOO_MANY_REQUESTS)
+}
+
+/// 500ms, 1s, 2s...
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500 * 2u64.pow(attempt))
+}
+
+fn retry_after(res: &Response) -> Option<Duration> {
+    res.headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
@@ -6,23 +6,23 @@ use serde::Deserialize;
 use twilight_model::channel::Attachment;
 use twilight_util::builder::embed::{EmbedBuilder, ImageSource};
 
-const URL: &str = "https://yandex.ru/images/search";
+use super::web::{Service, WebClient};
 
 /// Returns a captcha packet so not possible to use yandex at the end.
-pub async fn fetch(attachment: &Attachment) -> Result<SiteResponse> {
+pub async fn fetch(web: &WebClient, attachment: &Attachment) -> Result<SiteResponse> {
     lazy_static! {
         static ref RE: Regex = Regex::new(r#"data-state="(\{&quot;sites&quot;:.+?)"#).unwrap();
     }
 
     let form = multipart::Form::new().part(
         "upfile",
-        multipart::Part::bytes(super::get_bytes(&attachment.proxy_url).await?.to_vec())
+        multipart::Part::bytes(web.get_bytes(&attachment.proxy_url).await?.to_vec())
             .mime_str(attachment.content_type.as_deref().unwrap_or("image/jpeg"))?,
     );
 
-    let client = reqwest::Client::new();
-    let upload = client
-        .post(URL)
+    let url = web.url(Service::Yandex, "/images/search")?;
+    let request = web
+        .post(&url)
         .query(&[
             ("rpt", "imageview"),
             ("format", "json"),
@@ -31,20 +31,16 @@ pub async fn fetch(attachment: &Attachment) -> Result<SiteResponse> {
                 r#"{"blocks":[{"block":"b-page_type_search-by-image__link"}]}"#,
             ),
         ])
-        .multipart(form)
-        .send()
-        .await?
-        .json::<UploadResponse>()
-        .await?;
+        .multipart(form);
+    let upload = web.send(request).await?.json::<UploadResponse>().await?;
 
     ensure!(
         !upload.blocks.is_empty(),
         "Couldn't find the image on the upload request to Yandex!"
     );
 
-    let res = client
-        .get(format!("{}?{}", URL, upload.blocks[0].params.url))
-        .send()
+    let res = web
+        .send(web.get(format!("{}?{}", url, upload.blocks[0].params.url)))
         .await?
         .text()
         .await?;